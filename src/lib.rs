@@ -1,24 +1,194 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![recursion_limit = "512"]
 extern crate alloc;
 
 use alloc::vec::Vec;
 use stylus_sdk::{
-    alloy_primitives::{Address, B256, U256},
-    alloy_sol_types::sol,
-    block, call, contract, crypto, msg,
+    alloy_primitives::{Address, Bytes, B256, U256},
+    alloy_sol_types::{sol, SolCall},
+    abi, crypto,
     prelude::*,
+    storage::{StorageMap, StorageU256},
 };
 
-// ERC721 interface for NFT transfers
-sol_interface! {
+// Auction phases as returned by `phase()`
+const PHASE_NOT_STARTED: u8 = 0;
+const PHASE_COMMIT: u8 = 1;
+const PHASE_REVEAL: u8 = 2;
+const PHASE_ENDED: u8 = 3;
+const PHASE_FINALIZED: u8 = 4;
+
+// Auction outcomes as returned by `safe_outcome()`
+const OUTCOME_PENDING: u8 = 0;
+const OUTCOME_SOLD: u8 = 1;
+const OUTCOME_UNSOLD: u8 = 2;
+const OUTCOME_CANCELLED: u8 = 3;
+
+// Protocol fee is expressed in basis points and capped at 10%
+const BPS_DENOMINATOR: u64 = 10_000;
+const MAX_FEE_BPS: u64 = 1_000;
+
+// Shortest recovery grace a deployer may configure; see `recovery_grace` in storage
+const MIN_RECOVERY_GRACE: u64 = 24 * 60 * 60;
+
+// Allowed phase lengths: long enough to be fair, short enough not to lock the seller's approval for years
+const MIN_COMMIT_DURATION: u64 = 5 * 60;
+const MAX_COMMIT_DURATION: u64 = 90 * 24 * 60 * 60;
+const MIN_REVEAL_DURATION: u64 = 5 * 60;
+const MAX_REVEAL_DURATION: u64 = 90 * 24 * 60 * 60;
+
+// Reasons reported by `DepositForfeited`
+const FORFEIT_HASH_MISMATCH: u8 = 1;
+const FORFEIT_INSUFFICIENT_DEPOSIT: u8 = 2;
+const FORFEIT_NEVER_REVEALED: u8 = 3;
+const FORFEIT_UNSETTLED: u8 = 4;
+
+// Most entries `top_bids()` will return
+const MAX_TOP_BIDS: u64 = 50;
+
+// Commitment preimage layout `commitment_hash()` implements, reported by `commitment_scheme()`:
+// 1 = keccak(le(bid) || le(nonce)), 2 = keccak(bidder || be(bid) || be(nonce))
+const COMMITMENT_SCHEME: u64 = 2;
+
+// ecrecover precompile, used to verify `reveal_signed()` signatures
+const ECRECOVER: Address = Address::new([0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+// Most NFTs one auction can bundle; every id costs an ownership check and a transfer
+const MAX_BUNDLE_SIZE: usize = 20;
+
+// Longest one-off reveal rescue a seller can grant with `extend_reveal()`
+const MAX_REVEAL_RESCUE: u64 = 7 * 24 * 60 * 60;
+
+// Most sealed bids one address may hold in a `multi_bid` auction, primary one included
+const MAX_BIDS_PER_BIDDER: usize = 5;
+
+// Most auctions `get_details_batch()` will look up in one call
+const MAX_DETAILS_BATCH: usize = 50;
+
+// Bidder cap used when the constructor is given zero. Refunds, sweeps and cancellation walk
+// the bidder list, so it must stay small enough to iterate in one transaction
+const DEFAULT_MAX_BIDDERS: u64 = 500;
+
+// Bidders `finalize()` auto-credits itself; the rest go through `credit_losers()`
+const FINALIZE_CREDIT_CHUNK: u64 = 100;
+
+// Upper bound on anti-snipe extensions so the reveal phase can't be stalled indefinitely
+const MAX_REVEAL_EXTENSIONS: u64 = 3;
+
+// ERC721 interface for NFT transfers. External calls are encoded with these `sol!` call
+// types and sent through `view_call`/`mutating_call`.
+sol! {
     interface IERC721 {
-        function transferFrom(address from, address to, uint256 tokenId) external;
-        function ownerOf(uint256 tokenId) external view returns (address);
-        function getApproved(uint256 tokenId) external view returns (address);
+        function transferFrom(address from, address to, uint256 token_id) external;
+        function safeTransferFrom(address from, address to, uint256 token_id) external;
+        function ownerOf(uint256 token_id) external view returns (address);
+        function getApproved(uint256 token_id) external view returns (address);
         function isApprovedForAll(address owner, address operator) external view returns (bool);
     }
 }
 
+// ERC1155 interface for semi-fungible auction lots
+sol! {
+    interface IERC1155 {
+        function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data) external;
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function isApprovedForAll(address account, address operator) external view returns (bool);
+    }
+}
+
+// ERC2981 interface for creator royalties
+sol! {
+    interface IERC2981 {
+        function royaltyInfo(uint256 token_id, uint256 sale_price) external view returns (address, uint256);
+    }
+}
+
+// Bidding rules passed to `create_auction`, grouped to stay under the ABI argument limit
+sol! {
+    #[derive(AbiType)]
+    struct AuctionRules {
+        uint256 min_increment;
+        uint256 min_reveals;
+        uint256 min_commits;
+        uint256 max_bid;
+        bool allow_reserve_below_deposit;
+        bool forgiving_reveal;
+        bool forfeit_on_invalid;
+        bool auto_credit_losers;
+        bool ordered_reveal;
+        bool multi_bid;
+        uint256 commit_buffer;
+        uint256 settle_window;
+        bool nonrevealed_penalty_to_seller;
+        uint256 deposit_bps;
+        bool private_auction;
+        bytes32 allow_root;
+    }
+}
+
+// Descending-price sale opened by `finalize()` when the sealed auction ends unsold;
+// a zero `start_price` disables it
+sol! {
+    #[derive(AbiType)]
+    struct DutchFallback {
+        uint256 start_price;
+        uint256 floor_price;
+        uint256 decay_per_second;
+    }
+}
+
+// ERC20 interface for token-denominated auctions
+sol! {
+    interface IERC20 {
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function balanceOf(address account) external view returns (uint256);
+    }
+}
+
+// Events
+sol! {
+    event AuctionCreated(
+        uint256 indexed auctionId,
+        address indexed seller,
+        address indexed nft_contract,
+        uint256 token_id,
+        uint256 reserve_price,
+        uint256 commit_end,
+        uint256 reveal_end,
+        uint256 min_deposit,
+        uint256 created_block
+    );
+    event BidCommitted(uint256 indexed auctionId, address indexed bidder, bytes32 commitment, uint256 totalDeposit);
+    event CommitReplaced(uint256 indexed auctionId, address indexed bidder, bytes32 newCommitment);
+    event CommitCancelled(uint256 indexed auctionId, address indexed bidder, uint256 refunded);
+    event BidRevealed(uint256 indexed auctionId, address indexed bidder, uint256 bid);
+    event NewHighestBid(uint256 indexed auctionId, address indexed bidder, uint256 bid, address indexed previousBidder);
+    event RevealFailed(uint256 indexed auctionId, address indexed bidder);
+    event TieRejected(uint256 indexed auctionId, address indexed bidder, uint256 bid);
+    event BidRejectedCap(uint256 indexed auctionId, address indexed bidder, uint256 bid, uint256 maxBid);
+    event BidRejectedDeposit(uint256 indexed auctionId, address indexed bidder, uint256 bid, uint256 deposit);
+    event AuctionFinalized(uint256 indexed auctionId, address indexed winner, uint256 winningBid, bool sold);
+    event RefundWithdrawn(uint256 indexed auctionId, address indexed who, uint256 amount);
+    event ProceedsWithdrawn(uint256 indexed auctionId, address indexed beneficiary, uint256 amount);
+    event RevealExtended(uint256 indexed auctionId, uint256 newRevealEnd);
+    event CommitExtended(uint256 indexed auctionId, uint256 newCommitEnd, uint256 newRevealEnd);
+    event FeeCollected(uint256 indexed auctionId, address indexed recipient, uint256 amount);
+    event AuctionUnsold(uint256 indexed auctionId, uint256 token_id);
+    event NFTClaimed(uint256 indexed auctionId, address indexed winner);
+    event SellerNoLongerOwns(uint256 indexed auctionId, address indexed seller);
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+    event DepositForfeited(uint256 indexed auctionId, address indexed bidder, uint256 amount, uint8 reason);
+    event ReserveLowered(uint256 indexed auctionId, uint256 newReserve);
+    event OwnershipLostPaused(uint256 indexed auctionId);
+    event DutchOpened(uint256 indexed auctionId, uint256 startPrice, uint256 floorPrice, uint256 decayPerSecond);
+    event DutchPurchase(uint256 indexed auctionId, address indexed buyer, uint256 price);
+    event RunnerUpPromoted(uint256 indexed auctionId, address indexed previousWinner, address indexed newWinner, uint256 bid);
+    event SellerBondForfeited(uint256 indexed auctionId, uint256 amount, uint256 bidders);
+    event AllowlistAdded(uint256 indexed auctionId, address indexed bidder);
+    event AllowlistRemoved(uint256 indexed auctionId, address indexed bidder);
+}
+
 // Custom errors
 sol! {
     error NotOwner();
@@ -39,6 +209,38 @@ sol! {
     error AlreadyRevealed();
     error AuctionNotEnded();
     error NothingToWithdraw();
+    error NotWinner();
+    error AlreadySettled();
+    error NotSettled();
+    error IncorrectPayment();
+    error InvalidAmount();
+    error InvalidFee();
+    error Paused();
+    error NotPaused();
+    error AuctionNotFound();
+    error AuctionExists();
+    error Reentrancy();
+    error AuctionSold();
+    error AlreadyClaimed();
+    error ReserveBelowDeposit();
+    error UnexpectedEther();
+    error MixedPaymentTokens();
+    error EmptyCommitment();
+    error NoCommitmentFound();
+    error CommitmentMismatch();
+    error NotAllowlisted();
+    error ReserveCanOnlyDecrease();
+    error InvalidSignature();
+    error NotWethAuction();
+    error RevealExtensionUnavailable();
+    error BidderLimitReached();
+    error WeakNonce();
+    error RevealOrderViolation();
+    error NoRunnerUp();
+    error MultiBidDisabled();
+    error TooManyBids();
+    error AuctionHasCommits();
+    error CommitmentAlreadyUsed();
 }
 
 #[derive(SolidityError)]
@@ -61,57 +263,306 @@ pub enum SealedBidError {
     AlreadyRevealed(AlreadyRevealed),
     AuctionNotEnded(AuctionNotEnded),
     NothingToWithdraw(NothingToWithdraw),
+    NotWinner(NotWinner),
+    AlreadySettled(AlreadySettled),
+    NotSettled(NotSettled),
+    IncorrectPayment(IncorrectPayment),
+    InvalidAmount(InvalidAmount),
+    InvalidFee(InvalidFee),
+    Paused(Paused),
+    NotPaused(NotPaused),
+    AuctionNotFound(AuctionNotFound),
+    AuctionExists(AuctionExists),
+    Reentrancy(Reentrancy),
+    AuctionSold(AuctionSold),
+    AlreadyClaimed(AlreadyClaimed),
+    ReserveBelowDeposit(ReserveBelowDeposit),
+    UnexpectedEther(UnexpectedEther),
+    MixedPaymentTokens(MixedPaymentTokens),
+    EmptyCommitment(EmptyCommitment),
+    NoCommitmentFound(NoCommitmentFound),
+    CommitmentMismatch(CommitmentMismatch),
+    NotAllowlisted(NotAllowlisted),
+    ReserveCanOnlyDecrease(ReserveCanOnlyDecrease),
+    InvalidSignature(InvalidSignature),
+    NotWethAuction(NotWethAuction),
+    RevealExtensionUnavailable(RevealExtensionUnavailable),
+    BidderLimitReached(BidderLimitReached),
+    WeakNonce(WeakNonce),
+    RevealOrderViolation(RevealOrderViolation),
+    NoRunnerUp(NoRunnerUp),
+    MultiBidDisabled(MultiBidDisabled),
+    TooManyBids(TooManyBids),
+    AuctionHasCommits(AuctionHasCommits),
+    CommitmentAlreadyUsed(CommitmentAlreadyUsed),
 }
 
 // Storage
 sol_storage! {
-    #[entrypoint]
-    pub struct SealedBidAuction {
+    // State of a single auction
+    pub struct Auction {
         // basic auction metadata
         address seller;
+        address beneficiary;     // receives the sale proceeds
         address nft_contract;
-        uint256 token_id;
+        uint256 token_id;        // first id of the bundle; used for royalty lookups and events
+        uint256[] token_ids;     // every id sold together (a single-token auction has one)
+        bool is_erc1155;         // auctioned asset is an ERC1155 lot rather than an ERC721
+        uint256 amount;          // number of ERC1155 units in the lot
+        address payment_token;   // ERC20 used for deposits and payments (zero = ETH)
 
         // economic params
         uint256 reserve_price;   // min acceptable winning bid
         uint256 min_deposit;     // deposit required to commit
+        uint256 min_increment;   // a new highest bid must beat the current one by at least this much
+        uint256 min_reveals;     // fewer valid reveals than this and the auction fails
+        uint256 min_commits;     // fewer live commitments than this and the auction fails
+        uint256 max_bid;         // reveals above this are rejected (zero = unlimited)
+        bool allow_reserve_below_deposit; // the reserve may sit below min_deposit, at creation and in lower_reserve
+        bool forgiving_reveal;   // a mismatching reveal can be retried instead of forfeiting
+        bool forfeit_on_invalid; // a failed reveal forfeits the deposit instead of refunding it
+        bool auto_credit_losers; // finalize credits every remaining non-winning deposit the policy allows
+        bool ordered_reveal;     // valid reveals must arrive in non-increasing bid order
+        bool multi_bid;          // bidders may hold several sealed bids via `commit_additional()`
+        uint256 last_revealed_bid; // bid of the latest competing reveal, checked when ordered_reveal is set
+        bool nonrevealed_penalty_to_seller; // finalize credits forfeited deposits to the seller's refunds
+        uint256 deposit_bps;     // deposit must cover at least this share of the revealed bid
+        bool private_auction;    // only allowlisted addresses may commit
+        bytes32 allow_root;      // Merkle root of additional allowlisted addresses (zero = none)
+        bool second_price;       // winner pays the second-highest bid (Vickrey)
+        uint256 fee_bps;         // protocol fee taken from the sale price
+        address fee_recipient;   // receives the protocol fee
 
         // timelines (unix seconds)
         uint256 start_time;
+        uint256 created_block;   // block height at creation; reorg-robust anchor for indexers
         uint256 commit_end;      // end timestamp of commit phase
+        uint256 commit_buffer;   // commits are refused during this many final seconds of the commit phase
+        uint256 settle_window;   // time the winner gets to settle before the seller may promote the runner-up (zero = never)
+        uint256 settle_deadline; // set on promotion; before that the deadline is reveal_end + settle_window
         uint256 reveal_end;      // end timestamp of reveal phase
+        uint256 anti_snipe_window;   // late new-highest reveals push reveal_end by this much
+        uint256 reveal_extensions; // anti-snipe extensions applied so far
+        bool reveal_rescued;         // seller already used `extend_reveal()`
 
         // state
         bool finalized;
+        bool cancelled;          // seller cancelled instead of settling
+        bool paused;
+        uint256 paused_at;       // timestamp the current pause started
         address highest_bidder;
         uint256 highest_bid;
+        uint256 second_highest_bid;
+        address second_highest_bidder; // runner-up `promote_runner_up()` can make the winner
+        uint256 reveal_count;    // number of valid reveals
+        uint256 commit_count;    // bidders holding a commitment (cancelled commits drop out)
+        bool settled;            // winner has escrowed the full winning bid
+        bool sold;               // finalize awarded the NFT to the winner
+        bool nft_claimed;        // winner has pulled the NFT via claim_nft
+        uint256 settled_price;   // amount actually paid to the seller once the NFT was delivered
+        uint256 seller_bond;     // ETH the seller staked against cancelling after commits (ETH auctions only)
+        uint256 seller_proceeds; // sale proceeds credited on delivery of the NFT, not yet withdrawn
+        uint256 dutch_start_price; // dutch fallback config (zero start price = disabled)
+        uint256 dutch_floor_price;
+        uint256 dutch_decay;     // price drop per second
+        uint256 dutch_start;     // when finalize opened the dutch sale (zero = not open)
+        address dutch_buyer;     // who bought through the dutch sale
+        bool unsold_confirmed;   // seller acknowledged the no-sale via reclaim_unsold
 
         // mappings
-        mapping(address => bytes32) commitments; // commit hash => saved
-        mapping(address => uint256) deposits;    // total deposit posted by address
+        mapping(address => bytes32[]) commitments; // a bidder's sealed bids; index 0 is the primary one
+        mapping(address => uint256[]) bid_deposits; // deposit paid with each extra bid, aligned with commitments[1..]
+        mapping(address => uint256) revealed_mask; // bit i set once sealed bid i was validly revealed
+        mapping(address => uint256) deposits;    // escrow posted by address and not yet credited to refunds
         mapping(address => bool) revealed;       // whether address already revealed
         mapping(address => uint256) refunds;     // withdrawnable refunds
+        mapping(address => bool) has_committed;  // whether address is already in `bidders`
+        mapping(address => bool) forfeited;      // revealed with a mismatching hash
+        mapping(address => bool) swept;          // forfeited deposit already sent to seller
+        uint256 swept_cursor;                    // end of the furthest `bidders` slice swept so far
+        uint256 credit_cursor;                   // end of the furthest `bidders` slice auto-credited so far
+        mapping(address => uint256) revealed_bids; // highest validly revealed bid of each address
+        mapping(address => uint256) commit_time; // timestamp of each bidder's first commit, for tie-breaking
+        mapping(address => bool) allowed;        // allowlist consulted when private_auction is set
+        mapping(bytes32 => address) commitment_owner; // first address to commit each hash
+
+        // every address that has committed, in first-commit order
+        address[] bidders;
+    }
+
+    #[entrypoint]
+    pub struct SealedBidAuction {
+        address owner;                           // protocol admin (pause/unpause), separate from sellers
+        uint256 auction_count;                   // auctions created with sequential ids; also the next one
+        bool locked;                             // reentrancy guard for functions making external calls
+        mapping(address => uint256) total_deposits; // sum of every auction's `deposits` per payment token (zero = ETH)
+        uint256 recovery_grace;                  // wait after reveal_end before emergency_withdraw/finalize_unsold
+        address weth;                            // wrapped ETH on this chain (zero = no WETH support)
+        uint256 max_bidders;                     // most bidders one auction accepts (zero = DEFAULT_MAX_BIDDERS)
+        mapping(uint256 => Auction) auctions;
+    }
+}
+
+/// keccak256(bidder || bid || nonce). Words are big-endian to match Solidity's
+/// abi.encodePacked(address, uint256, uint256).
+fn commitment_hash(bidder: Address, bid: U256, nonce: U256) -> B256 {
+    let mut preimage: Vec<u8> = Vec::new();
+    preimage.extend_from_slice(bidder.as_slice());
+    preimage.extend_from_slice(&bid.to_be_bytes::<32>());
+    preimage.extend_from_slice(&nonce.to_be_bytes::<32>());
+    B256::from_slice(&crypto::keccak(preimage)[0..32])
+}
+
+/// Content-addressed auction id: keccak256(seller || nft_contract || token_id || start_time)
+fn auction_id_hash(seller: Address, nft_contract: Address, token_id: U256, start_time: U256) -> U256 {
+    let mut preimage: Vec<u8> = Vec::new();
+    preimage.extend_from_slice(seller.as_slice());
+    preimage.extend_from_slice(nft_contract.as_slice());
+    preimage.extend_from_slice(&token_id.to_be_bytes::<32>());
+    preimage.extend_from_slice(&start_time.to_be_bytes::<32>());
+    U256::from_be_bytes(crypto::keccak(preimage).0)
+}
+
+/// EIP-191 digest a bidder signs to authorize `reveal_signed()`:
+/// keccak256("\x19Ethereum Signed Message:\n32" || keccak256(contract || auction_id || bidder || bid || nonce))
+fn reveal_digest(contract: Address, auction_id: U256, bidder: Address, bid: U256, nonce: U256) -> B256 {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(contract.as_slice());
+    message.extend_from_slice(&auction_id.to_be_bytes::<32>());
+    message.extend_from_slice(bidder.as_slice());
+    message.extend_from_slice(&bid.to_be_bytes::<32>());
+    message.extend_from_slice(&nonce.to_be_bytes::<32>());
+    let mut preimage: Vec<u8> = b"\x19Ethereum Signed Message:\n32".to_vec();
+    preimage.extend_from_slice(&crypto::keccak(message)[0..32]);
+    B256::from_slice(&crypto::keccak(preimage)[0..32])
+}
+
+/// Whether `proof` links keccak256(account) to `root`, hashing sorted pairs at each level
+fn merkle_verify(root: B256, account: Address, proof: &[B256]) -> bool {
+    let mut node = B256::from_slice(&crypto::keccak(account.as_slice())[0..32]);
+    for sibling in proof {
+        let (a, b) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        let mut pair: Vec<u8> = Vec::new();
+        pair.extend_from_slice(a.as_slice());
+        pair.extend_from_slice(b.as_slice());
+        node = B256::from_slice(&crypto::keccak(pair)[0..32]);
     }
+    node == root
+}
+
+/// Whether `who`'s deposit is forfeited under the auction's policy: a bad reveal marked
+/// `forfeited`, or no reveal at all unless `auto_credit_losers` refunds non-revealers
+/// (which it does only without `forfeit_on_invalid`).
+fn deposit_forfeited(auction: &Auction, who: Address) -> bool {
+    if auction.forfeited.get(who) {
+        return true;
+    }
+    !auction.revealed.get(who) && (auction.forfeit_on_invalid.get() || !auction.auto_credit_losers.get())
+}
+
+/// Count `amount` of the auction's payment token into the escrow `totals`
+fn escrow_in(totals: &mut StorageMap<Address, StorageU256>, auction: &Auction, amount: U256) {
+    let token = auction.payment_token.get();
+    let total = totals.get(token);
+    totals.setter(token).set(total + amount);
+}
+
+/// Take `amount` of the auction's payment token back out of the escrow `totals`
+fn escrow_out(totals: &mut StorageMap<Address, StorageU256>, auction: &Auction, amount: U256) {
+    let token = auction.payment_token.get();
+    let total = totals.get(token);
+    totals.setter(token).set(total - amount);
 }
 
 #[public]
 impl SealedBidAuction {
-    /// Initialize auction. Called once after deployment.
-    pub fn new(
+    /// Set the protocol owner, the recovery grace period, the chain's WETH (zero if none) and the
+    /// per-auction bidder cap (zero for the default). Runs once, as part of the deployment.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        owner: Address,
+        recovery_grace: U256,
+        weth: Address,
+        max_bidders: U256,
+    ) -> Result<(), SealedBidError> {
+        if owner == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
+        if recovery_grace < U256::from(MIN_RECOVERY_GRACE) {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+        self.owner.set(owner);
+        self.recovery_grace.set(recovery_grace);
+        self.weth.set(weth);
+        self.max_bidders.set(max_bidders);
+        log(self.vm(), OwnershipTransferred {
+            previousOwner: Address::ZERO,
+            newOwner: owner,
+        });
+        Ok(())
+    }
+
+    /// Hand the owner role to `new_owner` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), SealedBidError> {
+        self.only_owner()?;
+        if new_owner == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        log(self.vm(), OwnershipTransferred {
+            previousOwner: previous_owner,
+            newOwner: new_owner,
+        });
+        Ok(())
+    }
+
+    /// Create a new auction for an NFT owned by the caller, who becomes its seller.
+    /// Attached ETH is held as the seller's bond (ETH auctions only).
+    /// Returns the id used to address the auction in every other call.
+    // the flat parameter list is the external ABI; optional features are already grouped into structs
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_auction(
         &mut self,
-        seller: Address,
         nft_contract: Address,
-        token_id: U256,
+        token_ids: Vec<U256>,
         reserve_price: U256,
         commit_duration: U256,
         reveal_duration: U256,
         min_deposit: U256,
-    ) -> Result<(), SealedBidError> {
-        if seller == Address::ZERO || nft_contract == Address::ZERO {
+        second_price: bool,
+        anti_snipe_window: U256,
+        payment_token: Address,
+        is_erc1155: bool,
+        amount: U256,
+        fee_bps: U256,
+        fee_recipient: Address,
+        beneficiary: Address,
+        deterministic_id: bool,
+        rules: AuctionRules,
+        dutch: DutchFallback,
+    ) -> Result<U256, SealedBidError> {
+        self.enter()?;
+        let seller = self.vm().msg_sender();
+        if nft_contract == Address::ZERO {
             return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
         }
 
-        if commit_duration == U256::ZERO || reveal_duration == U256::ZERO {
+        if commit_duration < U256::from(MIN_COMMIT_DURATION)
+            || commit_duration > U256::from(MAX_COMMIT_DURATION)
+            || reveal_duration < U256::from(MIN_REVEAL_DURATION)
+            || reveal_duration > U256::from(MAX_REVEAL_DURATION)
+        {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+        // keeps `reveal_end + window` far from overflow and each extension no longer than the phase itself
+        if anti_snipe_window > reveal_duration {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+        // same for `reveal_end + settle_window` and a promotion's fresh window
+        if rules.settle_window > U256::from(MAX_REVEAL_DURATION) {
             return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
         }
 
@@ -119,331 +570,3017 @@ impl SealedBidAuction {
             return Err(SealedBidError::InvalidCommit(InvalidCommit {}));
         }
 
-        // set state
-        self.seller.set(seller);
-        self.nft_contract.set(nft_contract);
-        self.token_id.set(token_id);
-        self.reserve_price.set(reserve_price);
-        self.min_deposit.set(min_deposit);
+        // a deposit larger than the reserve is usually a misconfiguration, so it must be opted into
+        if reserve_price < min_deposit && !rules.allow_reserve_below_deposit {
+            return Err(SealedBidError::ReserveBelowDeposit(ReserveBelowDeposit {}));
+        }
 
-        let now = U256::from(block::timestamp());
-        self.start_time.set(now);
-        self.commit_end.set(now + commit_duration);
-        self.reveal_end.set(now + commit_duration + reveal_duration);
+        if token_ids.is_empty() || token_ids.len() > MAX_BUNDLE_SIZE {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        for (i, id) in token_ids.iter().enumerate() {
+            if token_ids[..i].contains(id) {
+                return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+            }
+        }
+        let token_id = token_ids[0];
 
-        self.finalized.set(false);
-        self.highest_bidder.set(Address::ZERO);
-        self.highest_bid.set(U256::ZERO);
+        if is_erc1155 && amount == U256::ZERO {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
 
-        // Verify NFT ownership and approval
-        self.verify_nft_authorization(seller)?;
+        if fee_bps > U256::from(MAX_FEE_BPS) {
+            return Err(SealedBidError::InvalidFee(InvalidFee {}));
+        }
+        if fee_bps > U256::ZERO && fee_recipient == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
 
-        Ok(())
-    }
+        if rules.deposit_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
 
-    /// Commit a bid hash (keccak256(abi.encodePacked(bid, nonce))).
-    /// Must send at least `min_deposit` as msg.value. Multiple commits from same address add deposits,
-    /// but only the last commitment is considered (so discourage multiple commits).
-    pub fn commit(&mut self, commitment: B256) -> Result<(), SealedBidError> {
-        let now = U256::from(block::timestamp());
-        if now >= self.commit_end.get() {
-            return Err(SealedBidError::CommitPhaseOver(CommitPhaseOver {}));
+        if dutch.start_price > U256::ZERO
+            && (dutch.floor_price == U256::ZERO || dutch.floor_price > dutch.start_price)
+        {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
         }
 
-        let sender = msg::sender();
-        if commitment == B256::ZERO {
-            return Err(SealedBidError::InvalidCommit(InvalidCommit {}));
+        // the bond is paid back through `refunds`, which are denominated in the payment token
+        let bond = self.vm().msg_value();
+        if payment_token != Address::ZERO && bond > U256::ZERO {
+            return Err(SealedBidError::UnexpectedEther(UnexpectedEther {}));
         }
 
-        let value = msg::value();
-        if value < self.min_deposit.get() && self.deposits.get(sender) == U256::ZERO {
-            // If the caller hasn't deposited before, require at least min_deposit
-            return Err(SealedBidError::NoDeposit(NoDeposit {}));
+        let now = U256::from(self.vm().block_timestamp());
+        let count = self.auction_count.get();
+        let auction_id = if deterministic_id {
+            auction_id_hash(seller, nft_contract, token_id, now)
+        } else {
+            count
+        };
+        if self.auctions.get(auction_id).seller.get() != Address::ZERO {
+            return Err(SealedBidError::AuctionExists(AuctionExists {}));
+        }
+        if !deterministic_id {
+            self.auction_count.set(count + U256::from(1));
         }
 
-        // store/overwrite commitment
-        self.commitments.setter(sender).set(commitment);
+        let commit_end = now + commit_duration;
+        let reveal_end = commit_end + reveal_duration;
 
-        // accumulate deposits
-        if value > U256::ZERO {
-            let prev = self.deposits.get(sender);
-            self.deposits.setter(sender).set(prev + value);
+        // set state
+        let mut auction = self.auctions.setter(auction_id);
+        auction.seller.set(seller);
+        auction.beneficiary.set(if beneficiary == Address::ZERO { seller } else { beneficiary });
+        auction.nft_contract.set(nft_contract);
+        auction.token_id.set(token_id);
+        for id in token_ids {
+            auction.token_ids.push(id);
         }
+        auction.is_erc1155.set(is_erc1155);
+        auction.amount.set(amount);
+        auction.payment_token.set(payment_token);
+        auction.reserve_price.set(reserve_price);
+        auction.min_deposit.set(min_deposit);
+        auction.min_increment.set(rules.min_increment);
+        auction.min_reveals.set(rules.min_reveals);
+        auction.min_commits.set(rules.min_commits);
+        auction.max_bid.set(rules.max_bid);
+        auction.allow_reserve_below_deposit.set(rules.allow_reserve_below_deposit);
+        auction.forgiving_reveal.set(rules.forgiving_reveal);
+        auction.forfeit_on_invalid.set(rules.forfeit_on_invalid);
+        auction.auto_credit_losers.set(rules.auto_credit_losers);
+        auction.ordered_reveal.set(rules.ordered_reveal);
+        auction.multi_bid.set(rules.multi_bid);
+        auction.nonrevealed_penalty_to_seller.set(rules.nonrevealed_penalty_to_seller);
+        auction.deposit_bps.set(rules.deposit_bps);
+        auction.private_auction.set(rules.private_auction);
+        auction.allow_root.set(rules.allow_root);
+        auction.second_price.set(second_price);
+        auction.fee_bps.set(fee_bps);
+        auction.fee_recipient.set(fee_recipient);
+        auction.dutch_start_price.set(dutch.start_price);
+        auction.dutch_floor_price.set(dutch.floor_price);
+        auction.dutch_decay.set(dutch.decay_per_second);
+
+        auction.start_time.set(now);
+        let created_block = U256::from(auction.vm().block_number());
+        auction.created_block.set(created_block);
+        auction.commit_end.set(commit_end);
+        auction.commit_buffer.set(rules.commit_buffer);
+        auction.settle_window.set(rules.settle_window);
+        auction.reveal_end.set(reveal_end);
+        auction.anti_snipe_window.set(anti_snipe_window);
+        auction.seller_bond.set(bond);
+
+        // Verify NFT ownership and approval
+        self.verify_nft_authorization(auction_id, seller)?;
+
+        log(self.vm(), AuctionCreated {
+            auctionId: auction_id,
+            seller,
+            nft_contract,
+            token_id,
+            reserve_price,
+            commit_end,
+            reveal_end,
+            min_deposit,
+            created_block,
+        });
 
+        self.exit();
+        Ok(auction_id)
+    }
+
+    /// Commit a bid hash (keccak256(abi.encodePacked(msg.sender, bid, nonce))).
+    /// Binding the hash to the sender means a commitment copied from the mempool can't be revealed by anyone else.
+    /// Must send at least `min_deposit` as msg.value. Multiple commits from same address add deposits,
+    /// but only the last commitment is considered (so discourage multiple commits).
+    /// In ERC20 mode the first commit pulls `min_deposit` of the payment token via `transferFrom`
+    /// instead, so the bidder must approve the auction beforehand.
+    #[payable]
+    pub fn commit(&mut self, auction_id: U256, commitment: B256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        let sender = self.vm().msg_sender();
+        self.record_commit(auction_id, sender, sender, commitment, &[], None)?;
+        self.exit();
         Ok(())
     }
 
-    /// Reveal a previously committed bid.
-    /// `bid` must match the committed hash when combined with `nonce`:
-    /// keccak256(bid || nonce) == commitment
-    pub fn reveal(&mut self, bid: U256, nonce: U256) -> Result<(), SealedBidError> {
-        let now = U256::from(block::timestamp());
-        if now <= self.commit_end.get() {
-            return Err(SealedBidError::RevealNotOpen(RevealNotOpen {}));
-        }
-        if now >= self.reveal_end.get() {
-            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+    /// Commit on behalf of `bidder`: the caller funds the deposit but the commitment and
+    /// deposit are recorded under `bidder`, who alone can reveal it (the hash is bound to them).
+    /// Only a first commitment can be placed this way; once `bidder` holds one, only they can replace it.
+    #[payable]
+    pub fn commit_for(&mut self, auction_id: U256, bidder: Address, commitment: B256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        if bidder == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
         }
+        self.record_commit(auction_id, self.vm().msg_sender(), bidder, commitment, &[], None)?;
+        self.exit();
+        Ok(())
+    }
 
-        let sender = msg::sender();
-
-        if self.revealed.get(sender) {
-            return Err(SealedBidError::AlreadyRevealed(AlreadyRevealed {}));
-        }
+    /// Same as `commit`, with a Merkle proof that the caller is in the auction's `allow_root`.
+    /// Leaves are keccak256(address) and each level hashes the sorted pair of children.
+    #[payable]
+    pub fn commit_with_proof(&mut self, auction_id: U256, commitment: B256, proof: Vec<B256>) -> Result<(), SealedBidError> {
+        self.enter()?;
+        let sender = self.vm().msg_sender();
+        self.record_commit(auction_id, sender, sender, commitment, &proof, None)?;
+        self.exit();
+        Ok(())
+    }
 
-        let commitment = self.commitments.get(sender);
-        if commitment == B256::ZERO {
-            return Err(SealedBidError::InvalidCommit(InvalidCommit {}));
+    /// Commit to a WETH-denominated auction, pulling `amount` WETH via `transferFrom`
+    /// (at least `min_deposit` on the first commit). Unlike `commit`, which pulls exactly
+    /// `min_deposit` once, the bidder picks how much to lock and can add more on later calls.
+    /// The seller is paid in WETH like any other ERC20 auction.
+    pub fn commit_weth(&mut self, auction_id: U256, commitment: B256, amount: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        let weth = self.weth.get();
+        if weth == Address::ZERO || self.auctions.get(auction_id).payment_token.get() != weth {
+            return Err(SealedBidError::NotWethAuction(NotWethAuction {}));
         }
+        let sender = self.vm().msg_sender();
+        self.record_commit(auction_id, sender, sender, commitment, &[], Some(amount))?;
+        self.exit();
+        Ok(())
+    }
 
-        // Recompute keccak256(bid || nonce) and compare
-        let mut preimage: Vec<u8> = Vec::new();
-        preimage.extend_from_slice(&bid.as_le_bytes());
-        preimage.extend_from_slice(&nonce.as_le_bytes());
-        let computed = B256::from_slice(&crypto::keccak(preimage)[0..32]);
+    /// Add `msg.value` to the caller's deposit without touching their commitment.
+    /// Only available during the commit phase, to bidders who already committed, in ETH auctions.
+    #[payable]
+    pub fn top_up_deposit(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
 
-        if computed != commitment {
-            // invalid reveal: mark revealed so attacker cannot retry; deposit is forfeited
-            self.revealed.setter(sender).set(true);
-            // deposit remains in contract (forfeited)
-            return Err(SealedBidError::InvalidCommit(InvalidCommit {}));
+        let sender = self.vm().msg_sender();
+        if self.primary_commitment(auction_id, sender) == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
         }
 
-        // valid reveal
-        self.revealed.setter(sender).set(true);
-
-        // get deposit for this sender
-        let depos = self.deposits.get(sender);
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.payment_token.get() != Address::ZERO {
+            if self.vm().msg_value() > U256::ZERO {
+                return Err(SealedBidError::UnexpectedEther(UnexpectedEther {}));
+            }
+            return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
+        }
 
-        if depos < self.min_deposit.get() {
-            // insufficient deposit -> treat as invalid (forfeit)
+        let value = auction.vm().msg_value();
+        if value == U256::ZERO {
             return Err(SealedBidError::NoDeposit(NoDeposit {}));
         }
 
-        // Accept the revealed bid only if bid is greater than current highest.
-        if bid > self.highest_bid.get() {
-            // previous highest becomes refundable (its deposit + bid is refunded to previous highest bidder)
-            let prev_high = self.highest_bidder.get();
-            if prev_high != Address::ZERO {
-                // give previous bidder a withdrawable refund equal to their deposit + previous bid
-                // (we assume previous bid amount was not yet kept by seller)
-                let mut prev_ref = self.refunds.get(prev_high);
-                // Add previous bid amount + deposit previously held by the previous highest bidder.
-                // We don't store previous bidder's deposit separately here, so assume deposit tracked in deposits map.
-                let prev_deposit = self.deposits.get(prev_high);
-                prev_ref = prev_ref + self.highest_bid.get() + prev_deposit;
-                self.refunds.setter(prev_high).set(prev_ref);
-            }
+        let prev = auction.deposits.get(sender);
+        auction.deposits.setter(sender).set(prev + value);
+        escrow_in(&mut self.total_deposits, &auction, value);
+        Ok(())
+    }
 
-            // set new highest (and keep this bidder's deposit in contract until finalize or refund)
-            self.highest_bid.set(bid);
-            self.highest_bidder.set(sender);
+    /// ERC20 counterpart of `top_up_deposit`: pull `amount` of the payment token via
+    /// `transferFrom` into the caller's deposit, so it can cover a larger bid under `deposit_bps`.
+    pub fn top_up_token_deposit(&mut self, auction_id: U256, amount: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
 
-            // For the current revealer, we reduce their deposit by nothing now; funds stay locked
-            // actual funds transfer to seller happens in finalize
-        } else {
-            // Not a winning bid — allow withdraw later (bid + deposit). We'll store refund now.
-            let mut r = self.refunds.get(sender);
-            r = r + bid + depos;
-            self.refunds.setter(sender).set(r);
+        let sender = self.vm().msg_sender();
+        if self.primary_commitment(auction_id, sender) == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+
+        let payment_token = self.auctions.get(auction_id).payment_token.get();
+        if payment_token == Address::ZERO {
+            return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
+        }
+        if amount == U256::ZERO {
+            return Err(SealedBidError::NoDeposit(NoDeposit {}));
         }
+        self.pull_funds(payment_token, sender, amount)?;
 
+        let mut auction = self.auctions.setter(auction_id);
+        let prev = auction.deposits.get(sender);
+        auction.deposits.setter(sender).set(prev + amount);
+        escrow_in(&mut self.total_deposits, &auction, amount);
+        self.exit();
         Ok(())
     }
 
-    /// Finalize auction after reveal period. Transfers NFT to winner (if reserve met),
-    /// sends payments to seller, and unlocks refunds.
-    pub fn finalize(&mut self) -> Result<(), SealedBidError> {
-        let now = U256::from(block::timestamp());
-        if now < self.reveal_end.get() {
-            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+    /// Replace the caller's commitment and add `msg.value` to their deposit in one call.
+    /// Commit phase only (the `commit_buffer` applies), and only over an existing commitment.
+    /// ERC20 auctions take no ETH here; the commitment is simply replaced.
+    #[payable]
+    pub fn recommit(&mut self, auction_id: U256, new_commitment: B256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
+
+        let sender = self.vm().msg_sender();
+        let auction = self.auctions.get(auction_id);
+        let now = U256::from(self.vm().block_timestamp());
+        if auction.commit_end.get() - now < auction.commit_buffer.get() {
+            return Err(SealedBidError::CommitPhaseOver(CommitPhaseOver {}));
         }
-        if self.finalized.get() {
-            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        if new_commitment == B256::ZERO {
+            return Err(SealedBidError::EmptyCommitment(EmptyCommitment {}));
+        }
+        if self.primary_commitment(auction_id, sender) == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+        let value = self.vm().msg_value();
+        if auction.payment_token.get() != Address::ZERO && value > U256::ZERO {
+            return Err(SealedBidError::UnexpectedEther(UnexpectedEther {}));
+        }
+
+        self.check_commitment_unique(auction_id, sender, new_commitment, 0)?;
+        self.set_primary_commitment(auction_id, sender, new_commitment);
+        let mut auction = self.auctions.setter(auction_id);
+        if value > U256::ZERO {
+            let prev = auction.deposits.get(sender);
+            auction.deposits.setter(sender).set(prev + value);
+            escrow_in(&mut self.total_deposits, &auction, value);
         }
 
-        let seller = self.seller.get();
-        let winner = self.highest_bidder.get();
-        let winning_bid = self.highest_bid.get();
-        let reserve = self.reserve_price.get();
+        log(self.vm(), CommitReplaced {
+            auctionId: auction_id,
+            bidder: sender,
+            newCommitment: new_commitment,
+        });
+        Ok(())
+    }
 
-        // If there is a valid highest bid meeting reserve, settle
-        if winner != Address::ZERO && winning_bid >= reserve {
-            // Transfer NFT from seller -> winner
-            self.transfer_nft(seller, winner)?;
+    /// Add another sealed bid next to the caller's existing commitment (`multi_bid`
+    /// auctions only, same timing rules as `recommit`). Each extra bid carries its own
+    /// deposit of at least `min_deposit`: the attached ETH, or in ERC20 mode `min_deposit`
+    /// pulled via `transferFrom`. At reveal every bid competes backed by its own deposit.
+    #[payable]
+    pub fn commit_additional(&mut self, auction_id: U256, commitment: B256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
 
-            // Compute amount to send to seller: winning_bid
-            if winning_bid > U256::ZERO {
-                self.transfer_payment(seller, winning_bid)?;
-            }
+        let sender = self.vm().msg_sender();
+        let auction = self.auctions.get(auction_id);
+        if !auction.multi_bid.get() {
+            return Err(SealedBidError::MultiBidDisabled(MultiBidDisabled {}));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        if auction.commit_end.get() - now < auction.commit_buffer.get() {
+            return Err(SealedBidError::CommitPhaseOver(CommitPhaseOver {}));
+        }
+        if commitment == B256::ZERO {
+            return Err(SealedBidError::EmptyCommitment(EmptyCommitment {}));
+        }
+        if self.primary_commitment(auction_id, sender) == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+        if auction.commitments.get(sender).len() >= MAX_BIDS_PER_BIDDER {
+            return Err(SealedBidError::TooManyBids(TooManyBids {}));
+        }
+        let payment_token = auction.payment_token.get();
+        let min_deposit = auction.min_deposit.get();
+        if payment_token != Address::ZERO && self.vm().msg_value() > U256::ZERO {
+            return Err(SealedBidError::UnexpectedEther(UnexpectedEther {}));
+        }
+        let slot = auction.commitments.get(sender).len();
+        self.check_commitment_unique(auction_id, sender, commitment, slot)?;
 
-            // The auction contract may still hold deposits: give bidders ability to withdraw their refunds
-            // For the winner, any deposit they posted is refundable minus policy; here we choose to refund deposit.
-            let winner_deposit = self.deposits.get(winner);
-            if winner_deposit > U256::ZERO {
-                let prev = self.refunds.get(winner);
-                self.refunds.setter(winner).set(prev + winner_deposit);
-            }
+        let value = if payment_token == Address::ZERO {
+            self.vm().msg_value()
         } else {
-            // No valid winning bid: seller can reclaim the NFT (it remains with seller until transfer).
-            // Nothing to transfer. Optionally mark refunds for all revealers: everyone can withdraw their deposits + bids recorded.
-            // We will not iterate over bidders (no dynamic list). Deposits are withdrawable by callers via withdraw_refund().
+            self.pull_funds(payment_token, sender, min_deposit)?;
+            min_deposit
+        };
+        if value < min_deposit {
+            return Err(SealedBidError::NoDeposit(NoDeposit {}));
         }
 
-        self.finalized.set(true);
+        let mut auction = self.auctions.setter(auction_id);
+        auction.commitments.setter(sender).push(commitment);
+        auction.bid_deposits.setter(sender).push(value);
+        let total_deposit = auction.deposits.get(sender) + value;
+        auction.deposits.setter(sender).set(total_deposit);
+        escrow_in(&mut self.total_deposits, &auction, value);
+
+        log(self.vm(), BidCommitted {
+            auctionId: auction_id,
+            bidder: sender,
+            commitment,
+            totalDeposit: total_deposit,
+        });
+        self.exit();
         Ok(())
     }
 
-    /// Withdraw refunds (bid + deposit) available to caller.
-    pub fn withdraw_refund(&mut self) -> Result<(), SealedBidError> {
-        let caller = msg::sender();
-        let amount = self.refunds.get(caller);
-        if amount == U256::ZERO {
-            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+    /// Withdraw the caller's commitment during the commit phase.
+    /// The full deposit is credited to `refunds` and the caller is dropped from `bidders`.
+    pub fn cancel_commit(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
+
+        let sender = self.vm().msg_sender();
+        if self.primary_commitment(auction_id, sender) == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
         }
 
-        // zero out before transfer (checks-effects-interactions)
-        self.refunds.setter(caller).set(U256::ZERO);
+        // every sealed bid goes, extra ones included
+        let mut auction = self.auctions.setter(auction_id);
+        let mut commitments = auction.commitments.setter(sender);
+        while !commitments.is_empty() {
+            commitments.erase_last();
+        }
+        let mut bid_deposits = auction.bid_deposits.setter(sender);
+        while !bid_deposits.is_empty() {
+            bid_deposits.erase_last();
+        }
 
-        let result = call::transfer_eth(caller, amount);
-        if result.is_err() {
-            // restore on failure
-            let prev = self.refunds.get(caller);
-            self.refunds.setter(caller).set(prev + amount);
-            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+        let deposit = auction.deposits.get(sender);
+        auction.deposits.setter(sender).set(U256::ZERO);
+        escrow_out(&mut self.total_deposits, &auction, deposit);
+        if deposit > U256::ZERO {
+            let prev = auction.refunds.get(sender);
+            auction.refunds.setter(sender).set(prev + deposit);
         }
 
-        Ok(())
-    }
+        auction.has_committed.setter(sender).set(false);
+        auction.commit_time.setter(sender).set(U256::ZERO);
+        let commit_count = auction.commit_count.get();
+        auction.commit_count.set(commit_count - U256::from(1));
+        self.remove_bidder(auction_id, sender);
 
-    /// Allow seller to stop auction early (only if not finalized)
-    pub fn cancel_auction(&mut self) -> Result<(), SealedBidError> {
-        if msg::sender() != self.seller.get() {
-            return Err(SealedBidError::OnlySeller(OnlySeller {}));
-        }
-        if self.finalized.get() {
-            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
-        }
+        log(self.vm(), CommitCancelled {
+            auctionId: auction_id,
+            bidder: sender,
+            refunded: deposit,
+        });
 
-        // Mark finalized so no further actions expected; refunds can be withdrawn by callers
-        self.finalized.set(true);
         Ok(())
     }
 
-    /// Helper views
-    pub fn get_details(&self) -> (Address, Address, U256, U256, U256, U256, U256, bool, Address, U256) {
-        (
-            self.seller.get(),
-            self.nft_contract.get(),
-            self.token_id.get(),
-            self.reserve_price.get(),
-            self.min_deposit.get(),
-            self.commit_end.get(),
-            self.reveal_end.get(),
-            self.finalized.get(),
-            self.highest_bidder.get(),
-            self.highest_bid.get(),
-        )
+    /// Reveal a previously committed bid.
+    /// `bid` must match the committed hash when combined with the caller and `nonce`:
+    /// keccak256(msg.sender || bid || nonce) == commitment
+    /// A mismatching reveal does not revert: it logs `RevealFailed` and, under
+    /// `forfeit_on_invalid`, forfeits the deposit (otherwise the deposit is refunded).
+    /// In forgiving mode it reverts with `CommitmentMismatch` and the bidder may try again.
+    /// A bidder holding several sealed bids reveals their primary one, as `reveal_at(0)` would.
+    pub fn reveal(&mut self, auction_id: U256, bid: U256, nonce: U256) -> Result<(), SealedBidError> {
+        self.reveal_primary(auction_id, self.vm().msg_sender(), bid, nonce)
     }
 
-    pub fn seller(&self) -> Address {
-        self.seller.get()
-    }
-    pub fn nft_contract(&self) -> Address {
-        self.nft_contract.get()
-    }
-    pub fn token_id(&self) -> U256 {
-        self.token_id.get()
-    }
-    pub fn reserve_price(&self) -> U256 {
-        self.reserve_price.get()
-    }
-    pub fn min_deposit(&self) -> U256 {
-        self.min_deposit.get()
-    }
-    pub fn commit_end(&self) -> U256 {
-        self.commit_end.get()
-    }
-    pub fn reveal_end(&self) -> U256 {
-        self.reveal_end.get()
-    }
-    pub fn finalized(&self) -> bool {
-        self.finalized.get()
-    }
-    pub fn highest_bidder(&self) -> Address {
-        self.highest_bidder.get()
+    /// Reveal the caller's sealed bid at `index` (see `commitment_at`) in a `multi_bid`
+    /// auction. Each bid competes on its own, backed by the deposit paid with it
+    /// (`bid_deposit_at`), and bids may be revealed one by one in any order. A mismatch
+    /// reverts with `CommitmentMismatch` whatever the reveal mode.
+    pub fn reveal_at(&mut self, auction_id: U256, index: U256, bid: U256, nonce: U256) -> Result<(), SealedBidError> {
+        let sender = self.vm().msg_sender();
+        if self.auctions.get(auction_id).commitments.get(sender).len() > 1 {
+            return self.record_reveal_at(auction_id, sender, index, bid, nonce);
+        }
+        if index != U256::ZERO {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        self.record_reveal(auction_id, sender, bid, nonce)
     }
-    pub fn highest_bid(&self) -> U256 {
-        self.highest_bid.get()
+
+    /// Reveal every sealed bid the caller holds in one call: `bids[i]` and `nonces[i]`
+    /// open `commitment_at(i)`, and all of them must be given. This is `reveal_at` for each
+    /// index in turn, so any failing bid reverts the whole call.
+    pub fn reveal_all(&mut self, auction_id: U256, bids: Vec<U256>, nonces: Vec<U256>) -> Result<(), SealedBidError> {
+        let sender = self.vm().msg_sender();
+        let count = self.auctions.get(auction_id).commitments.get(sender).len();
+        if count == 0 {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+        if bids.len() != count || nonces.len() != count {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        if count == 1 {
+            return self.record_reveal(auction_id, sender, bids[0], nonces[0]);
+        }
+        for i in 0..count {
+            self.record_reveal_at(auction_id, sender, U256::from(i), bids[i], nonces[i])?;
+        }
+        Ok(())
     }
 
-    /// Allow caller to check their refundable amount
-    pub fn refund_of(&self, who: Address) -> U256 {
-        self.refunds.get(who)
+    /// Reveal on behalf of `bidder`, e.g. by a relayer paying the gas.
+    /// `signature` is `bidder`'s 65-byte EIP-191 (`personal_sign`) signature over
+    /// keccak256(contract || auction_id || bidder || bid || nonce); the reveal then
+    /// proceeds exactly as if `bidder` had called `reveal()`.
+    pub fn reveal_signed(
+        &mut self,
+        auction_id: U256,
+        bidder: Address,
+        bid: U256,
+        nonce: U256,
+        signature: abi::Bytes,
+    ) -> Result<(), SealedBidError> {
+        if bidder == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
+        let digest = reveal_digest(self.vm().contract_address(), auction_id, bidder, bid, nonce);
+        if self.recover_signer(digest, &signature) != Some(bidder) {
+            return Err(SealedBidError::InvalidSignature(InvalidSignature {}));
+        }
+        self.reveal_primary(auction_id, bidder, bid, nonce)
     }
-}
 
-impl SealedBidAuction {
-    /// Verifies seller owns NFT and contract is approved to transfer it
-    fn verify_nft_authorization(&mut self, seller: Address) -> Result<(), SealedBidError> {
-        let nft_contract = IERC721::new(self.nft_contract.get());
-        let token_id = self.token_id.get();
+    /// Winner escrows the rest of their bid after the reveal phase.
+    /// Bidders only lock a deposit at commit time, so the winner must send
+    /// `highest_bid - deposit` (zero if the deposit already covers the bid)
+    /// before `finalize()` can pay the seller.
+    /// In ERC20 mode the top-up is pulled via `transferFrom` and no ETH may be attached.
+    #[payable]
+    pub fn settle(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_ended(auction_id)?;
 
-        // owner_of
-        let owner_res = nft_contract.owner_of(call::Call::new_in(self), token_id);
-        match owner_res {
-            Ok(owner) => {
-                if owner != seller {
-                    return Err(SealedBidError::NotNFTOwner(NotNFTOwner {}));
-                }
-            }
-            Err(_) => return Err(SealedBidError::NFTTransferFailed(NFTTransferFailed {})),
+        let auction = self.auctions.get(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
         }
 
-        let contract_address = contract::address();
-        let approved_res = nft_contract.get_approved(call::Call::new_in(self), token_id);
-        let approved_for_all_res = nft_contract.is_approved_for_all(call::Call::new_in(self), seller, contract_address);
-
-        let is_approved = match approved_res {
-            Ok(approved) => approved == contract_address,
-            Err(_) => false,
-        };
+        let sender = self.vm().msg_sender();
+        if sender != auction.highest_bidder.get() {
+            return Err(SealedBidError::NotWinner(NotWinner {}));
+        }
+        if auction.settled.get() {
+            return Err(SealedBidError::AlreadySettled(AlreadySettled {}));
+        }
 
-        let is_approved_for_all = match approved_for_all_res {
-            Ok(ap) => ap,
-            Err(_) => false,
+        let deposit = auction.deposits.get(sender);
+        let owed = auction.highest_bid.get().saturating_sub(deposit);
+        let payment_token = auction.payment_token.get();
+        let value = if payment_token == Address::ZERO {
+            self.vm().msg_value()
+        } else {
+            if self.vm().msg_value() != U256::ZERO {
+                return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
+            }
+            if owed > U256::ZERO {
+                self.pull_funds(payment_token, sender, owed)?;
+            }
+            owed
         };
-
-        if !is_approved && !is_approved_for_all {
-            return Err(SealedBidError::NotApproved(NotApproved {}));
+        if value != owed {
+            return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
         }
 
+        // the winner's escrow now covers the full winning bid
+        let mut auction = self.auctions.setter(auction_id);
+        auction.deposits.setter(sender).set(deposit + value);
+        escrow_in(&mut self.total_deposits, &auction, value);
+        auction.settled.set(true);
+        self.exit();
         Ok(())
     }
 
-    /// Transfer NFT with safety check
-    fn transfer_nft(&mut self, from: Address, to: Address) -> Result<(), SealedBidError> {
-        let nft_contract = IERC721::new(self.nft_contract.get());
-        let token_id = self.token_id.get();
-        let res = nft_contract.transfer_from(call::Call::new_in(self), from, to, token_id);
-        if res.is_err() {
-            return Err(SealedBidError::NFTTransferFailed(NFTTransferFailed {}));
-        }
-        Ok(())
-    }
+    /// Finalize auction after reveal period. Records the winner (if reserve met)
+    /// and unlocks refunds. The sale price stays in the contract until `claim_nft()`
+    /// delivers the NFT, so a receiver that rejects it can't block settlement and a
+    /// seller who can no longer deliver is never paid.
+    /// A winning bid must have been escrowed via `settle()` first; the seller is paid
+    /// the sale price out of the winner's escrow, never from other bidders' funds.
+    /// The sale price is the winning bid, or in second-price mode the second-highest
+    /// bid clamped to at least the reserve.
+    pub fn finalize(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_ended(auction_id)?;
 
-    /// Transfer payment (ETH) to `to`
-    fn transfer_payment(&self, to: Address, amount: U256) -> Result<(), SealedBidError> {
-        if to == Address::ZERO {
-            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        let auction = self.auctions.get(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
         }
-        if amount == U256::ZERO {
-            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+
+        let winner = auction.highest_bidder.get();
+        let winning_bid = auction.highest_bid.get();
+        let winner_escrow = auction.deposits.get(winner);
+
+        // If there is a valid highest bid meeting reserve (with enough reveals), settle
+        let enough_commits = auction.commit_count.get() >= auction.min_commits.get();
+        let settled = auction.settled.get();
+        let seller = auction.seller.get();
+        let mut sold = self.meets_sale_conditions(auction_id);
+
+        // The NFT stays with the seller until claimed; if it has moved away the sale can't
+        // happen, so the winner is refunded in full instead of being paid out. Walking away
+        // from a sale like this costs the seller their bond, as cancelling would.
+        let mut seller_defaulted = false;
+        if sold && !self.seller_still_owns(auction_id, seller) {
+            log(self.vm(), SellerNoLongerOwns {
+                auctionId: auction_id,
+                seller,
+            });
+            sold = false;
+            seller_defaulted = true;
         }
-        let res = call::transfer_eth(to, amount);
-        if res.is_err() {
-            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+
+        if sold {
+            // the escrow can also be gone if the winner used emergency_withdraw
+            if !settled || winner_escrow < winning_bid {
+                return Err(SealedBidError::NotSettled(NotSettled {}));
+            }
+
+            // the price itself is paid out by `claim_nft()` once the NFT is delivered
+            let price = self.sale_price(auction_id);
+
+            // The winner's escrow (deposit + settle top-up) covers at least the winning bid.
+            // Their refund is the deposit beyond the bid (`settle()` asks for no top-up then)
+            // plus, in second-price mode, the gap between the bid and the sale price.
+            // Together that is exactly `winner_escrow - price`; nothing else is credited.
+            let deposit_excess = winner_escrow - winning_bid;
+            let price_discount = winning_bid - price;
+            let winner_excess = deposit_excess + price_discount;
+            let mut auction = self.auctions.setter(auction_id);
+            auction.deposits.setter(winner).set(U256::ZERO);
+            escrow_out(&mut self.total_deposits, &auction, winner_escrow);
+            if winner_excess > U256::ZERO {
+                let prev = auction.refunds.get(winner);
+                auction.refunds.setter(winner).set(prev + winner_excess);
+            }
+        } else {
+            // No valid winning bid: seller can reclaim the NFT (it remains with seller until transfer).
+            // Nothing to transfer. Revealed losers were already credited their deposits during reveal.
+            // The other bidders' deposits are handled by the pass over `bidders` below.
+            // A would-be winner of a failed auction gets back everything they escrowed.
+            if winner != Address::ZERO {
+                let mut auction = self.auctions.setter(auction_id);
+                auction.deposits.setter(winner).set(U256::ZERO);
+                escrow_out(&mut self.total_deposits, &auction, winner_escrow);
+                if winner_escrow > U256::ZERO {
+                    let prev = auction.refunds.get(winner);
+                    auction.refunds.setter(winner).set(prev + winner_escrow);
+                }
+            }
+        }
+
+        // too little participation fails the auction outright: nobody's deposit is kept,
+        // not even a non-revealer's
+        if !enough_commits {
+            self.refund_deposits(auction_id);
+        } else {
+            let len = self.auctions.get(auction_id).bidders.len();
+            self.settle_losers_range(auction_id, 0, len.min(FINALIZE_CREDIT_CHUNK as usize));
+        }
+
+        if sold {
+            log(self.vm(), AuctionFinalized {
+                auctionId: auction_id,
+                winner,
+                winningBid: winning_bid,
+                sold: true,
+            });
+        } else {
+            log(self.vm(), AuctionFinalized {
+                auctionId: auction_id,
+                winner: Address::ZERO,
+                winningBid: U256::ZERO,
+                sold: false,
+            });
+        }
+
+        // an honest finalize hands the bond back, sold or not
+        self.release_bond(auction_id, seller_defaulted);
+
+        let mut auction = self.auctions.setter(auction_id);
+        auction.sold.set(sold);
+        auction.finalized.set(true);
+
+        // an unsold (not cancelled) auction falls back to a descending-price sale if configured
+        let dutch_start_price = auction.dutch_start_price.get();
+        if !sold && dutch_start_price > U256::ZERO {
+            let now = U256::from(auction.vm().block_timestamp());
+            auction.dutch_start.set(now);
+            log(auction.vm(), DutchOpened {
+                auctionId: auction_id,
+                startPrice: dutch_start_price,
+                floorPrice: auction.dutch_floor_price.get(),
+                decayPerSecond: auction.dutch_decay.get(),
+            });
+        }
+        self.exit();
+        Ok(())
+    }
+
+    /// Buy the NFT through the dutch fallback at the current `dutch_price()`.
+    /// ETH auctions must attach at least the price; any excess is credited to the
+    /// buyer's refund balance. ERC20 auctions pull exactly the price via `transferFrom`.
+    /// The NFT moves straight from the seller and the price is paid out like a sale.
+    #[payable]
+    pub fn buy_now_dutch(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if auction.dutch_start.get() == U256::ZERO || auction.unsold_confirmed.get() {
+            return Err(SealedBidError::AuctionNotActive(AuctionNotActive {}));
+        }
+        if auction.dutch_buyer.get() != Address::ZERO {
+            return Err(SealedBidError::AuctionSold(AuctionSold {}));
+        }
+        let seller = auction.seller.get();
+        let payment_token = auction.payment_token.get();
+        let price = self.dutch_price(auction_id);
+
+        let buyer = self.vm().msg_sender();
+        if payment_token == Address::ZERO {
+            if self.vm().msg_value() < price {
+                return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
+            }
+        } else {
+            if self.vm().msg_value() != U256::ZERO {
+                return Err(SealedBidError::IncorrectPayment(IncorrectPayment {}));
+            }
+            self.pull_funds(payment_token, buyer, price)?;
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+        auction.dutch_buyer.set(buyer);
+        let excess = if payment_token == Address::ZERO { auction.vm().msg_value() - price } else { U256::ZERO };
+        if excess > U256::ZERO {
+            let prev = auction.refunds.get(buyer);
+            auction.refunds.setter(buyer).set(prev + excess);
+        }
+
+        self.transfer_nft(auction_id, seller, buyer)?;
+        self.pay_out(auction_id, price)?;
+
+        log(self.vm(), DutchPurchase {
+            auctionId: auction_id,
+            buyer,
+            price,
+        });
+        self.exit();
+        Ok(())
+    }
+
+    /// Replace a winner who let `settle_deadline()` pass without settling (seller only).
+    /// The winner's deposit is forfeited to the seller's refund balance and the runner-up
+    /// becomes the winner at their own revealed bid, with a fresh `settle_window` to settle.
+    /// Their deposit was refunded when they were outbid, so they escrow the full bid. Only
+    /// one promotion is possible, since no bid below the runner-up's is tracked.
+    pub fn promote_runner_up(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_ended(auction_id)?;
+
+        let deadline = self.settle_deadline(auction_id);
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        if auction.settled.get() {
+            return Err(SealedBidError::AlreadySettled(AlreadySettled {}));
+        }
+        let now = U256::from(auction.vm().block_timestamp());
+        if deadline == U256::ZERO || now <= deadline {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        let winner = auction.highest_bidder.get();
+        let runner_up = auction.second_highest_bidder.get();
+        let runner_up_bid = auction.second_highest_bid.get();
+        if winner == Address::ZERO || runner_up == Address::ZERO || runner_up_bid < auction.reserve_price.get() {
+            return Err(SealedBidError::NoRunnerUp(NoRunnerUp {}));
+        }
+
+        // the defaulting winner compensates the seller
+        let seller = auction.seller.get();
+        let deposit = auction.deposits.get(winner);
+        auction.deposits.setter(winner).set(U256::ZERO);
+        auction.forfeited.setter(winner).set(true);
+        escrow_out(&mut self.total_deposits, &auction, deposit);
+        let prev = auction.refunds.get(seller);
+        auction.refunds.setter(seller).set(prev + deposit);
+        log(auction.vm(), DepositForfeited {
+            auctionId: auction_id,
+            bidder: winner,
+            amount: deposit,
+            reason: FORFEIT_UNSETTLED,
+        });
+
+        // the runner-up pays what they bid, also in second-price mode
+        auction.highest_bidder.set(runner_up);
+        auction.highest_bid.set(runner_up_bid);
+        auction.second_highest_bidder.set(Address::ZERO);
+        let window = auction.settle_window.get();
+        let Some(deadline) = now.checked_add(window) else {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        };
+        auction.settle_deadline.set(deadline);
+        log(self.vm(), RunnerUpPromoted {
+            auctionId: auction_id,
+            previousWinner: winner,
+            newWinner: runner_up,
+            bid: runner_up_bid,
+        });
+        Ok(())
+    }
+
+    /// Escape hatch for an auction `finalize()` can't complete (e.g. the winner never
+    /// settles or a payout keeps reverting). Once `recovery_grace` has passed after
+    /// `reveal_end`, anyone can close it as unsold without any state-changing external calls; the
+    /// highest bidder's escrow becomes refundable and the other deposits are handled
+    /// exactly as an unsold `finalize()` would.
+    pub fn finalize_unsold(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let grace = self.recovery_grace.get();
+        let auction = self.auctions.get(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        if now <= auction.reveal_end.get() + grace {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+
+        let winner = auction.highest_bidder.get();
+        let seller = auction.seller.get();
+        // a seller who moved the NFT away can't dodge the bond by waiting for this path
+        let seller_defaulted = winner != Address::ZERO && !self.seller_still_owns(auction_id, seller);
+        let mut auction = self.auctions.setter(auction_id);
+        if winner != Address::ZERO {
+            let escrow = auction.deposits.get(winner);
+            auction.deposits.setter(winner).set(U256::ZERO);
+            escrow_out(&mut self.total_deposits, &auction, escrow);
+            if escrow > U256::ZERO {
+                let prev = auction.refunds.get(winner);
+                auction.refunds.setter(winner).set(prev + escrow);
+            }
+        }
+
+        // finalization closes emergency_withdraw, so everyone else needs the same treatment as in finalize
+        if auction.commit_count.get() < auction.min_commits.get() {
+            self.refund_deposits(auction_id);
+        } else {
+            let len = auction.bidders.len();
+            self.settle_losers_range(auction_id, 0, len.min(FINALIZE_CREDIT_CHUNK as usize));
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+        auction.sold.set(false);
+        auction.finalized.set(true);
+        log(self.vm(), AuctionFinalized {
+            auctionId: auction_id,
+            winner: Address::ZERO,
+            winningBid: U256::ZERO,
+            sold: false,
+        });
+        self.release_bond(auction_id, seller_defaulted);
+        Ok(())
+    }
+
+    /// Withdraw refunds (deposits and any winner excess) available to caller.
+    pub fn withdraw_refund(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        let caller = self.vm().msg_sender();
+        self.pay_refund(auction_id, caller, caller)?;
+
+        self.exit();
+        Ok(())
+    }
+
+    /// Withdraw the caller's refund to another address.
+    pub fn withdraw_refund_to(&mut self, auction_id: U256, recipient: Address) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        if recipient == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
+        self.pay_refund(auction_id, self.vm().msg_sender(), recipient)?;
+
+        self.exit();
+        Ok(())
+    }
+
+    /// Send `who`'s refund to `who`. Callable by anyone, so keepers can push refunds
+    /// for bidders who can't (or would rather not) pay gas themselves.
+    pub fn push_refund(&mut self, auction_id: U256, who: Address) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        self.pay_refund(auction_id, who, who)?;
+
+        self.exit();
+        Ok(())
+    }
+
+    /// Send the sale proceeds credited once the NFT was delivered to the auction's beneficiary.
+    /// Callable by anyone; the funds only ever go to the beneficiary.
+    pub fn withdraw_proceeds(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        let amount = auction.seller_proceeds.get();
+        if amount == U256::ZERO {
+            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+        let beneficiary = auction.beneficiary.get();
+
+        // zero out before transfer (checks-effects-interactions); a failed transfer reverts it
+        self.auctions.setter(auction_id).seller_proceeds.set(U256::ZERO);
+        // WETH auctions pay out in WETH: unwrapping would need WETH to call back in with the
+        // ETH, and the contract is not reentrant
+        self.transfer_payment(auction_id, beneficiary, amount)?;
+
+        log(self.vm(), ProceedsWithdrawn {
+            auctionId: auction_id,
+            beneficiary,
+            amount,
+        });
+        self.exit();
+        Ok(())
+    }
+
+    /// Withdraw the caller's refunds from several auctions in a single transfer.
+    /// All listed auctions must share the same payment currency.
+    pub fn batch_withdraw(&mut self, auction_ids: Vec<U256>) -> Result<(), SealedBidError> {
+        self.enter()?;
+
+        let caller = self.vm().msg_sender();
+        let mut token = Address::ZERO;
+        let mut total = U256::ZERO;
+        let mut paid: Vec<(U256, U256)> = Vec::new();
+        for (i, auction_id) in auction_ids.iter().enumerate() {
+            let auction_id = *auction_id;
+            self.require_exists(auction_id)?;
+            self.require_not_paused(auction_id)?;
+
+            let payment_token = self.auctions.get(auction_id).payment_token.get();
+            if i == 0 {
+                token = payment_token;
+            } else if payment_token != token {
+                return Err(SealedBidError::MixedPaymentTokens(MixedPaymentTokens {}));
+            }
+
+            // zero every balance before the single transfer (checks-effects-interactions)
+            let mut auction = self.auctions.setter(auction_id);
+            let amount = auction.refunds.get(caller);
+            if amount == U256::ZERO {
+                continue;
+            }
+            auction.refunds.setter(caller).set(U256::ZERO);
+            total += amount;
+            paid.push((auction_id, amount));
+        }
+
+        if total == U256::ZERO {
+            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+
+        if !self.send_currency(token, caller, total) {
+            // restore on failure
+            for (auction_id, amount) in paid {
+                let mut auction = self.auctions.setter(auction_id);
+                let prev = auction.refunds.get(caller);
+                auction.refunds.setter(caller).set(prev + amount);
+            }
+            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+        }
+
+        for (auction_id, amount) in paid {
+            log(self.vm(), RefundWithdrawn {
+                auctionId: auction_id,
+                who: caller,
+                amount,
+            });
+        }
+
+        self.exit();
+        Ok(())
+    }
+
+    /// Recover the caller's escrow when the auction is wedged: once `recovery_grace` has
+    /// passed after `reveal_end` without finalization, the caller's remaining deposit is
+    /// credited to `refunds` for `withdraw_refund()`. Deposits forfeited under the auction's
+    /// policy (see `sweep_forfeited`) stay forfeited.
+    pub fn emergency_withdraw(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let grace = self.recovery_grace.get();
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        let now = U256::from(auction.vm().block_timestamp());
+        if now <= auction.reveal_end.get() + grace {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+
+        let caller = auction.vm().msg_sender();
+        let deposit = auction.deposits.get(caller);
+        if deposit == U256::ZERO || deposit_forfeited(&auction, caller) {
+            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+
+        auction.deposits.setter(caller).set(U256::ZERO);
+        escrow_out(&mut self.total_deposits, &auction, deposit);
+        let prev = auction.refunds.get(caller);
+        auction.refunds.setter(caller).set(prev + deposit);
+        Ok(())
+    }
+
+    /// Pull the NFT from the seller to the winner after a successful finalize, then pay the
+    /// sale out: fee and royalty are sent and the beneficiary's share is credited for
+    /// `withdraw_proceeds()`. The winner or the seller may call it; the NFT only goes to the winner.
+    /// If the seller no longer holds the NFT or has revoked the approval, the sale is
+    /// undone instead: the winner is credited the price they paid and the auction counts as unsold.
+    pub fn claim_nft(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if !auction.finalized.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        let winner = auction.highest_bidder.get();
+        let seller = auction.seller.get();
+        let sender = self.vm().msg_sender();
+        if !auction.sold.get() || (sender != winner && sender != seller) {
+            return Err(SealedBidError::NotWinner(NotWinner {}));
+        }
+        if auction.nft_claimed.get() {
+            return Err(SealedBidError::AlreadyClaimed(AlreadyClaimed {}));
+        }
+        let price = self.sale_price(auction_id);
+
+        // an error here would revert the refund, so a failed delivery returns Ok
+        if self.verify_nft_authorization(auction_id, seller).is_err() {
+            let mut auction = self.auctions.setter(auction_id);
+            auction.sold.set(false);
+            let prev = auction.refunds.get(winner);
+            auction.refunds.setter(winner).set(prev + price);
+            log(self.vm(), SellerNoLongerOwns {
+                auctionId: auction_id,
+                seller,
+            });
+            self.exit();
+            return Ok(());
+        }
+
+        self.auctions.setter(auction_id).nft_claimed.set(true);
+        self.transfer_nft(auction_id, seller, winner)?;
+        self.pay_out(auction_id, price)?;
+
+        log(self.vm(), NFTClaimed {
+            auctionId: auction_id,
+            winner,
+        });
+        self.exit();
+        Ok(())
+    }
+
+    /// Confirm an unsold outcome (seller only, after finalize). The NFT never left the
+    /// seller, so this only emits `AuctionUnsold` for indexers; repeat calls are no-ops.
+    pub fn reclaim_unsold(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if !auction.finalized.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        if auction.sold.get() || auction.dutch_buyer.get() != Address::ZERO {
+            return Err(SealedBidError::AuctionSold(AuctionSold {}));
+        }
+        if auction.unsold_confirmed.get() {
+            return Ok(());
+        }
+
+        auction.unsold_confirmed.set(true);
+        log(auction.vm(), AuctionUnsold {
+            auctionId: auction_id,
+            token_id: auction.token_id.get(),
+        });
+        Ok(())
+    }
+
+    /// Allow seller to stop auction early (only if not finalized, and only before the
+    /// reveal phase ends so a legitimate winner can't be rugged). Every committer's
+    /// outstanding deposit is credited to `refunds`, and so is an equal share of the
+    /// seller's bond. Without any commitments the bond goes back to the seller.
+    pub fn cancel_auction(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if self.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        if U256::from(self.vm().block_timestamp()) >= auction.reveal_end.get() {
+            return Err(SealedBidError::AuctionNotActive(AuctionNotActive {}));
+        }
+
+        self.refund_deposits(auction_id);
+        // cancelling on bidders who already committed costs the seller their bond
+        let forfeit = self.auctions.get(auction_id).commit_count.get() > U256::ZERO;
+        self.release_bond(auction_id, forfeit);
+
+        // Mark finalized so no further actions expected; refunds can be withdrawn by callers
+        let mut auction = self.auctions.setter(auction_id);
+        auction.finalized.set(true);
+        auction.cancelled.set(true);
+        Ok(())
+    }
+
+    /// Abort an auction nobody has committed to (seller only, any time before finalize).
+    /// No funds are involved, so this is the clean way out; once a commitment exists it
+    /// reverts and `cancel_auction` with its refunds is the only option.
+    pub fn cancel_if_empty(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        if auction.commit_count.get() != U256::ZERO {
+            return Err(SealedBidError::AuctionHasCommits(AuctionHasCommits {}));
+        }
+
+        auction.finalized.set(true);
+        auction.cancelled.set(true);
+        self.release_bond(auction_id, false);
+        Ok(())
+    }
+
+    /// Allow `accounts` to commit to a private auction (seller only)
+    pub fn add_to_allowlist(&mut self, auction_id: U256, accounts: Vec<Address>) -> Result<(), SealedBidError> {
+        self.set_allowed(auction_id, accounts, true)
+    }
+
+    /// Revoke commit rights in a private auction (seller only). Existing commitments are kept.
+    pub fn remove_from_allowlist(&mut self, auction_id: U256, accounts: Vec<Address>) -> Result<(), SealedBidError> {
+        self.set_allowed(auction_id, accounts, false)
+    }
+
+    /// Give bidders more time to commit (seller only, during the commit phase).
+    /// Both deadlines move by `extra`, so the reveal window keeps its length. The commit
+    /// phase may not grow past `MAX_COMMIT_DURATION` from `start_time`.
+    pub fn extend_commit(&mut self, auction_id: U256, extra: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if self.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        self.require_commit_phase(auction_id)?;
+        if extra == U256::ZERO {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+
+        // the extended commit phase must still fit within MAX_COMMIT_DURATION, as at creation
+        let auction = self.auctions.get(auction_id);
+        let (Some(new_commit_end), Some(new_reveal_end)) =
+            (auction.commit_end.get().checked_add(extra), auction.reveal_end.get().checked_add(extra))
+        else {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        };
+        if new_commit_end - auction.start_time.get() > U256::from(MAX_COMMIT_DURATION) {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+        auction.commit_end.set(new_commit_end);
+        auction.reveal_end.set(new_reveal_end);
+
+        log(self.vm(), CommitExtended {
+            auctionId: auction_id,
+            newCommitEnd: new_commit_end,
+            newRevealEnd: new_reveal_end,
+        });
+        Ok(())
+    }
+
+    /// Rescue an auction nobody has revealed in yet (e.g. after a UI outage): push
+    /// `reveal_end` out by `extra`, at most `MAX_REVEAL_RESCUE`. Seller only, during the
+    /// reveal phase, and only once per auction.
+    pub fn extend_reveal(&mut self, auction_id: U256, extra: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if self.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        self.require_reveal_phase(auction_id)?;
+        if extra == U256::ZERO || extra > U256::from(MAX_REVEAL_RESCUE) {
+            return Err(SealedBidError::InvalidDuration(InvalidDuration {}));
+        }
+        let auction = self.auctions.get(auction_id);
+        if auction.reveal_count.get() > U256::ZERO || auction.reveal_rescued.get() {
+            return Err(SealedBidError::RevealExtensionUnavailable(RevealExtensionUnavailable {}));
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+        let new_end = auction.reveal_end.get() + extra;
+        auction.reveal_end.set(new_end);
+        auction.reveal_rescued.set(true);
+        log(self.vm(), RevealExtended {
+            auctionId: auction_id,
+            newRevealEnd: new_end,
+        });
+        Ok(())
+    }
+
+    /// Lower the reserve to attract bids (seller only, during the commit phase).
+    /// Raising it would be unfair to bidders who already committed, so it can only go down.
+    pub fn lower_reserve(&mut self, auction_id: U256, new_reserve: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if self.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        if new_reserve >= auction.reserve_price.get() {
+            return Err(SealedBidError::ReserveCanOnlyDecrease(ReserveCanOnlyDecrease {}));
+        }
+        if new_reserve < auction.min_deposit.get() && !auction.allow_reserve_below_deposit.get() {
+            return Err(SealedBidError::ReserveBelowDeposit(ReserveBelowDeposit {}));
+        }
+        self.require_commit_phase(auction_id)?;
+
+        self.auctions.setter(auction_id).reserve_price.set(new_reserve);
+        log(self.vm(), ReserveLowered {
+            auctionId: auction_id,
+            newReserve: new_reserve,
+        });
+        Ok(())
+    }
+
+    /// Send forfeited deposits (a bad reveal, or no reveal unless `auto_credit_losers`
+    /// refunds it) to the seller (seller only, after finalize). Only `bidder_at(start)` up to
+    /// `count` entries are processed, so large auctions can be swept over several calls.
+    /// Each deposit is swept at most once, so overlapping ranges are harmless.
+    pub fn sweep_forfeited(&mut self, auction_id: U256, start: U256, count: U256) -> Result<(), SealedBidError> {
+        self.enter()?;
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        let seller = auction.seller.get();
+        if self.vm().msg_sender() != seller {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        if !auction.finalized.get() || auction.cancelled.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+
+        let (from, to) = self.bidder_range(auction_id, start, count)?;
+        let total = self.collect_forfeited(auction_id, from, to);
+        let mut auction = self.auctions.setter(auction_id);
+        if U256::from(to) > auction.swept_cursor.get() {
+            auction.swept_cursor.set(U256::from(to));
+        }
+        // a slice without forfeits is fine: it just moves the cursor along
+        if total > U256::ZERO {
+            self.transfer_payment(auction_id, seller, total)?;
+        }
+        self.exit();
+        Ok(())
+    }
+
+    /// Finish the per-bidder pass `finalize()` started, for `count` bidders from `bidder_at(start)`:
+    /// the `auto_credit_losers` credits and the `nonrevealed_penalty_to_seller` compensation.
+    /// Anyone can call it. Deposits already credited or swept are skipped, so overlapping
+    /// ranges don't pay twice.
+    pub fn credit_losers(&mut self, auction_id: U256, start: U256, count: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if !auction.finalized.get() || auction.cancelled.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        if !auction.auto_credit_losers.get() && !auction.nonrevealed_penalty_to_seller.get() {
+            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+
+        let (from, to) = self.bidder_range(auction_id, start, count)?;
+        self.settle_losers_range(auction_id, from, to);
+        Ok(())
+    }
+
+    /// Freeze state-changing entry points (owner only). Views stay callable.
+    pub fn pause(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.only_owner()?;
+
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        if auction.paused.get() {
+            return Err(SealedBidError::Paused(Paused {}));
+        }
+
+        auction.paused.set(true);
+        let now = U256::from(auction.vm().block_timestamp());
+        auction.paused_at.set(now);
+        Ok(())
+    }
+
+    /// Circuit breaker anyone can trip: if the seller no longer holds the auctioned NFT,
+    /// pause the auction so nobody commits into a sale that can't happen. Returns whether
+    /// the seller still owns it. The owner can `unpause` to let `finalize` close it unsold.
+    pub fn check_nft_ownership(&mut self, auction_id: U256) -> Result<bool, SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        if auction.finalized.get() {
+            return Err(SealedBidError::AuctionAlreadyFinalized(AuctionAlreadyFinalized {}));
+        }
+        let seller = auction.seller.get();
+        if self.seller_still_owns(auction_id, seller) {
+            return Ok(true);
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+        if !auction.paused.get() {
+            auction.paused.set(true);
+            let now = U256::from(auction.vm().block_timestamp());
+            auction.paused_at.set(now);
+            log(self.vm(), OwnershipLostPaused { auctionId: auction_id });
+        }
+        Ok(false)
+    }
+
+    /// Resume the auction (owner only). Deadlines that hadn't passed when the pause
+    /// started are pushed back by the paused duration so bidders don't lose time.
+    pub fn unpause(&mut self, auction_id: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.only_owner()?;
+
+        let mut auction = self.auctions.setter(auction_id);
+        if !auction.paused.get() {
+            return Err(SealedBidError::NotPaused(NotPaused {}));
+        }
+
+        let paused_at = auction.paused_at.get();
+        let elapsed = U256::from(auction.vm().block_timestamp()) - paused_at;
+        let commit_end = auction.commit_end.get();
+        if paused_at < commit_end {
+            auction.commit_end.set(commit_end + elapsed);
+        }
+        let reveal_end = auction.reveal_end.get();
+        if paused_at < reveal_end {
+            auction.reveal_end.set(reveal_end + elapsed);
+        }
+
+        auction.paused.set(false);
+        auction.paused_at.set(U256::ZERO);
+        Ok(())
+    }
+
+    /// Protocol owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Number of auctions created with sequential ids, which run from 0 to `auction_count() - 1`.
+    /// Deterministic ids come from `compute_auction_id()` and are not counted.
+    pub fn auction_count(&self) -> U256 {
+        self.auction_count.get()
+    }
+
+    /// Id `create_auction` assigns with `deterministic_id` set, so it can be known before the
+    /// transaction confirms. `token_id` is the first id of the bundle and `start_time` the
+    /// timestamp of the creating block.
+    pub fn compute_auction_id(&self, seller: Address, nft_contract: Address, token_id: U256, start_time: U256) -> U256 {
+        auction_id_hash(seller, nft_contract, token_id, start_time)
+    }
+
+    /// Deposits in `token` (zero for ETH) held across all auctions and not yet credited to
+    /// refunds, paid out or swept
+    pub fn total_deposits(&self, token: Address) -> U256 {
+        self.total_deposits.get(token)
+    }
+
+    /// Wrapped ETH accepted by `commit_weth()` (zero if not configured)
+    pub fn weth(&self) -> Address {
+        self.weth.get()
+    }
+
+    /// Wait after `reveal_end` before `emergency_withdraw()` and `finalize_unsold()` open up
+    pub fn recovery_grace(&self) -> U256 {
+        self.recovery_grace.get()
+    }
+
+    /// Most bidders a single auction accepts; further first-time commits revert
+    pub fn max_bidders(&self) -> U256 {
+        let max_bidders = self.max_bidders.get();
+        if max_bidders == U256::ZERO {
+            U256::from(DEFAULT_MAX_BIDDERS)
+        } else {
+            max_bidders
+        }
+    }
+
+    /// ETH held by the contract. It should cover `total_deposits(0)`, unwithdrawn ETH refunds and
+    /// proceeds, and the price of every ETH sale finalized but not yet delivered by `claim_nft()`
+    pub fn contract_eth_balance(&self) -> U256 {
+        self.vm().balance(self.vm().contract_address())
+    }
+
+    /// Helper views
+    pub fn get_details(&self, auction_id: U256) -> (Address, Address, U256, U256, U256, U256, U256, bool, Address, U256) {
+        let auction = self.auctions.get(auction_id);
+        (
+            auction.seller.get(),
+            auction.nft_contract.get(),
+            auction.token_id.get(),
+            auction.reserve_price.get(),
+            auction.min_deposit.get(),
+            auction.commit_end.get(),
+            auction.reveal_end.get(),
+            auction.finalized.get(),
+            auction.highest_bidder.get(),
+            auction.highest_bid.get(),
+        )
+    }
+
+    /// Settings clients need to render an auction, kept out of `get_details()` so its shape
+    /// stays stable: (payment_token, second_price, private_auction, fee_bps, min_increment).
+    /// `private_auction` is also true when only an `allow_root` was set.
+    pub fn get_config(&self, auction_id: U256) -> (Address, bool, bool, U256, U256) {
+        let auction = self.auctions.get(auction_id);
+        (
+            auction.payment_token.get(),
+            auction.second_price.get(),
+            auction.private_auction.get() || auction.allow_root.get() != B256::ZERO,
+            auction.fee_bps.get(),
+            auction.min_increment.get(),
+        )
+    }
+
+    /// What `finalize()` would do right now: (will_sell, winner, seller_proceeds, royalty, fee).
+    /// Uses the current standings, reserve, fee and royalty config without changing state.
+    /// The proceeds, royalty and fee are paid out when `claim_nft()` delivers the NFT.
+    /// A sale also needs the winner to have escrowed their bid via `settle()`; until then
+    /// `finalize()` reverts rather than closing unsold.
+    pub fn preview_settlement(&self, auction_id: U256) -> (bool, Address, U256, U256, U256) {
+        let seller = self.auctions.get(auction_id).seller.get();
+        if !self.meets_sale_conditions(auction_id) || !self.seller_still_owns(auction_id, seller) {
+            return (false, Address::ZERO, U256::ZERO, U256::ZERO, U256::ZERO);
+        }
+        let winner = self.auctions.get(auction_id).highest_bidder.get();
+        let (fee, _, royalty, proceeds) = self.split_sale(auction_id, self.sale_price(auction_id));
+        (true, winner, proceeds, royalty, fee)
+    }
+
+    /// `get_details()` for several auctions at once, as parallel arrays (one per field,
+    /// entry `i` belongs to `auction_ids[i]`): seller, nft_contract, token_id,
+    /// reserve_price, min_deposit, commit_end, reveal_end, finalized, highest_bidder,
+    /// highest_bid. At most `MAX_DETAILS_BATCH` ids per call.
+    #[allow(clippy::type_complexity)]
+    pub fn get_details_batch(
+        &self,
+        auction_ids: Vec<U256>,
+    ) -> Result<
+        (
+            Vec<Address>,
+            Vec<Address>,
+            Vec<U256>,
+            Vec<U256>,
+            Vec<U256>,
+            Vec<U256>,
+            Vec<U256>,
+            Vec<bool>,
+            Vec<Address>,
+            Vec<U256>,
+        ),
+        SealedBidError,
+    > {
+        if auction_ids.len() > MAX_DETAILS_BATCH {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        let mut out = (
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        for auction_id in auction_ids {
+            let auction = self.auctions.get(auction_id);
+            out.0.push(auction.seller.get());
+            out.1.push(auction.nft_contract.get());
+            out.2.push(auction.token_id.get());
+            out.3.push(auction.reserve_price.get());
+            out.4.push(auction.min_deposit.get());
+            out.5.push(auction.commit_end.get());
+            out.6.push(auction.reveal_end.get());
+            out.7.push(auction.finalized.get());
+            out.8.push(auction.highest_bidder.get());
+            out.9.push(auction.highest_bid.get());
+        }
+        Ok(out)
+    }
+
+    /// Everything a front end needs in one call, as a flat tuple:
+    /// 0 seller, 1 nft_contract, 2 token_id, 3 reserve_price, 4 min_deposit,
+    /// 5 commit_end, 6 reveal_end, 7 finalized, 8 highest_bidder, 9 highest_bid,
+    /// 10 start_time, 11 phase, 12 time_until_commit_end, 13 time_until_reveal_end,
+    /// 14 reveal_count
+    #[allow(clippy::type_complexity)]
+    pub fn status(
+        &self,
+        auction_id: U256,
+    ) -> (Address, Address, U256, U256, U256, U256, U256, bool, Address, U256, U256, U256, U256, U256, U256) {
+        let auction = self.auctions.get(auction_id);
+        (
+            auction.seller.get(),
+            auction.nft_contract.get(),
+            auction.token_id.get(),
+            auction.reserve_price.get(),
+            auction.min_deposit.get(),
+            auction.commit_end.get(),
+            auction.reveal_end.get(),
+            auction.finalized.get(),
+            auction.highest_bidder.get(),
+            auction.highest_bid.get(),
+            auction.start_time.get(),
+            self.phase(auction_id),
+            self.time_until_commit_end(auction_id),
+            self.time_until_reveal_end(auction_id),
+            auction.reveal_count.get(),
+        )
+    }
+
+    pub fn seller(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).seller.get()
+    }
+    pub fn beneficiary(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).beneficiary.get()
+    }
+    pub fn nft_contract(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).nft_contract.get()
+    }
+    pub fn token_id(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).token_id.get()
+    }
+    /// Every token id in the auction's bundle
+    pub fn token_ids(&self, auction_id: U256) -> Vec<U256> {
+        self.bundle(auction_id)
+    }
+    pub fn is_erc1155(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).is_erc1155.get()
+    }
+    pub fn amount(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).amount.get()
+    }
+    pub fn payment_token(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).payment_token.get()
+    }
+    pub fn reserve_price(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).reserve_price.get()
+    }
+    pub fn min_deposit(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).min_deposit.get()
+    }
+    pub fn min_increment(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).min_increment.get()
+    }
+    pub fn min_reveals(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).min_reveals.get()
+    }
+    pub fn min_commits(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).min_commits.get()
+    }
+    /// Bidders currently holding a commitment
+    pub fn commit_count(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).commit_count.get()
+    }
+    pub fn max_bid(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).max_bid.get()
+    }
+    pub fn forgiving_reveal(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).forgiving_reveal.get()
+    }
+    pub fn forfeit_on_invalid(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).forfeit_on_invalid.get()
+    }
+    pub fn auto_credit_losers(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).auto_credit_losers.get()
+    }
+    pub fn ordered_reveal(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).ordered_reveal.get()
+    }
+    pub fn multi_bid(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).multi_bid.get()
+    }
+    pub fn nonrevealed_penalty_to_seller(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).nonrevealed_penalty_to_seller.get()
+    }
+    pub fn deposit_bps(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).deposit_bps.get()
+    }
+    pub fn private_auction(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).private_auction.get()
+    }
+    pub fn allow_root(&self, auction_id: U256) -> B256 {
+        self.auctions.get(auction_id).allow_root.get()
+    }
+
+    /// Whether `who` is on the auction's allowlist
+    pub fn is_allowlisted(&self, auction_id: U256, who: Address) -> bool {
+        self.auctions.get(auction_id).allowed.get(who)
+    }
+    pub fn reveal_count(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).reveal_count.get()
+    }
+    /// Block number the auction was created in
+    pub fn created_block(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).created_block.get()
+    }
+    pub fn commit_end(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).commit_end.get()
+    }
+    pub fn commit_buffer(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).commit_buffer.get()
+    }
+    pub fn reveal_end(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).reveal_end.get()
+    }
+    pub fn anti_snipe_window(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).anti_snipe_window.get()
+    }
+    pub fn reveal_extensions(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).reveal_extensions.get()
+    }
+    pub fn finalized(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).finalized.get()
+    }
+    pub fn cancelled(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).cancelled.get()
+    }
+    pub fn paused(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).paused.get()
+    }
+    pub fn highest_bidder(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).highest_bidder.get()
+    }
+    pub fn highest_bid(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).highest_bid.get()
+    }
+    pub fn settled(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).settled.get()
+    }
+    /// Seller bond still held; zero once it was returned or distributed
+    pub fn seller_bond(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).seller_bond.get()
+    }
+    /// Proceeds waiting for `withdraw_proceeds()`
+    pub fn seller_proceeds(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).seller_proceeds.get()
+    }
+    /// Amount the seller received, net of protocol fee and royalty (zero until the NFT is delivered)
+    pub fn settled_price(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).settled_price.get()
+    }
+    pub fn sold(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).sold.get()
+    }
+    pub fn nft_claimed(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).nft_claimed.get()
+    }
+    pub fn second_price(&self, auction_id: U256) -> bool {
+        self.auctions.get(auction_id).second_price.get()
+    }
+    pub fn second_highest_bid(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).second_highest_bid.get()
+    }
+    pub fn second_highest_bidder(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).second_highest_bidder.get()
+    }
+    pub fn settle_window(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).settle_window.get()
+    }
+
+    /// When the current winner's time to `settle()` runs out (zero if promotion is disabled)
+    pub fn settle_deadline(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        let window = auction.settle_window.get();
+        if window == U256::ZERO {
+            return U256::ZERO;
+        }
+        let promoted = auction.settle_deadline.get();
+        if promoted != U256::ZERO {
+            promoted
+        } else {
+            auction.reveal_end.get().saturating_add(window)
+        }
+    }
+    pub fn fee_bps(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).fee_bps.get()
+    }
+    pub fn fee_recipient(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).fee_recipient.get()
+    }
+
+    /// Allow caller to check their refundable amount
+    pub fn refund_of(&self, auction_id: U256, who: Address) -> U256 {
+        self.auctions.get(auction_id).refunds.get(who)
+    }
+
+    /// Whether `withdraw_refund()` would pay `who` something right now (refunds are
+    /// frozen while the auction is paused)
+    pub fn can_withdraw(&self, auction_id: U256, who: Address) -> bool {
+        let auction = self.auctions.get(auction_id);
+        !auction.paused.get() && auction.refunds.get(who) > U256::ZERO
+    }
+
+    /// Whether `emergency_withdraw()` would release `who`'s deposit right now: the auction
+    /// is unfinalized, `recovery_grace` has passed since `reveal_end`, and a deposit is held
+    pub fn can_emergency_withdraw(&self, auction_id: U256, who: Address) -> bool {
+        let auction = self.auctions.get(auction_id);
+        let now = U256::from(self.vm().block_timestamp());
+        auction.seller.get() != Address::ZERO
+            && !auction.finalized.get()
+            && now > auction.reveal_end.get() + self.recovery_grace.get()
+            && auction.deposits.get(who) > U256::ZERO
+            && !deposit_forfeited(&auction, who)
+    }
+
+    /// Total deposit currently held for `who`
+    pub fn deposit_of(&self, auction_id: U256, who: Address) -> U256 {
+        self.auctions.get(auction_id).deposits.get(who)
+    }
+
+    /// Primary commitment stored for `who` (zero if none)
+    pub fn commitment_of(&self, auction_id: U256, who: Address) -> B256 {
+        self.primary_commitment(auction_id, who)
+    }
+
+    /// Number of sealed bids `who` holds; above one only in `multi_bid` auctions
+    pub fn commitment_count(&self, auction_id: U256, who: Address) -> U256 {
+        U256::from(self.auctions.get(auction_id).commitments.get(who).len())
+    }
+
+    /// `who`'s sealed bid at `index` (zero if out of range)
+    pub fn commitment_at(&self, auction_id: U256, who: Address, index: U256) -> B256 {
+        self.auctions.get(auction_id).commitments.get(who).get(index).unwrap_or(B256::ZERO)
+    }
+
+    /// Deposit still backing `who`'s sealed bid at `index` (zero if out of range or released)
+    pub fn bid_deposit_at(&self, auction_id: U256, who: Address, index: U256) -> U256 {
+        if index >= U256::from(self.auctions.get(auction_id).commitments.get(who).len()) {
+            return U256::ZERO;
+        }
+        self.bid_deposit(auction_id, who, index.to::<usize>())
+    }
+
+    /// Whether `who`'s sealed bid at `index` has been validly revealed
+    pub fn is_bid_revealed(&self, auction_id: U256, who: Address, index: U256) -> bool {
+        index < U256::from(MAX_BIDS_PER_BIDDER)
+            && self.auctions.get(auction_id).revealed_mask.get(who) & (U256::from(1) << index.to::<usize>()) != U256::ZERO
+    }
+
+    /// `who`'s full financial position in one call:
+    /// (deposit, refund credit, revealed, is current highest bidder)
+    pub fn escrow_of(&self, auction_id: U256, who: Address) -> (U256, U256, bool, bool) {
+        let auction = self.auctions.get(auction_id);
+        (
+            auction.deposits.get(who),
+            auction.refunds.get(who),
+            auction.revealed.get(who),
+            who != Address::ZERO && auction.highest_bidder.get() == who,
+        )
+    }
+
+    /// When `who` first committed (zero if they hold no commitment); earlier commits win ties
+    pub fn commit_time_of(&self, auction_id: U256, who: Address) -> U256 {
+        self.auctions.get(auction_id).commit_time.get(who)
+    }
+
+    /// Bid revealed by `who` (zero unless they made a valid reveal)
+    pub fn revealed_bid_of(&self, auction_id: U256, who: Address) -> U256 {
+        self.auctions.get(auction_id).revealed_bids.get(who)
+    }
+
+    /// Up to `n` (capped at `MAX_TOP_BIDS`) highest revealed bids, sorted descending.
+    /// Walks every bidder, so this is meant for off-chain calls rather than other contracts.
+    pub fn top_bids(&self, auction_id: U256, n: U256) -> (Vec<Address>, Vec<U256>) {
+        let limit = n.min(U256::from(MAX_TOP_BIDS)).to::<usize>();
+        let auction = self.auctions.get(auction_id);
+        let mut top: Vec<(Address, U256)> = Vec::new();
+        for i in 0..auction.bidders.len() {
+            let who = auction.bidders.get(i).unwrap_or(Address::ZERO);
+            let bid = auction.revealed_bids.get(who);
+            if bid == U256::ZERO {
+                continue;
+            }
+            let pos = top.iter().position(|(_, b)| bid > *b).unwrap_or(top.len());
+            if pos < limit {
+                top.insert(pos, (who, bid));
+                top.truncate(limit);
+            }
+        }
+        top.into_iter().unzip()
+    }
+
+    /// Whether `who` has already revealed
+    pub fn is_revealed(&self, auction_id: U256, who: Address) -> bool {
+        self.auctions.get(auction_id).revealed.get(who)
+    }
+
+    /// Commitment `bidder` should submit for `bid` and `nonce`, using the same layout
+    /// `reveal()` checks against. Lets clients double-check an off-chain hash before committing.
+    pub fn compute_commitment(&self, bidder: Address, bid: U256, nonce: U256) -> B256 {
+        commitment_hash(bidder, bid, nonce)
+    }
+
+    /// Advisory nonce seed: keccak256(contract || bidder || block timestamp). It is tied to
+    /// this deployment so the same value isn't reused across auction contracts, but anyone
+    /// can compute it, so wallets must mix in their own randomness before committing.
+    pub fn suggested_nonce(&self, bidder: Address) -> U256 {
+        let mut preimage: Vec<u8> = Vec::new();
+        preimage.extend_from_slice(self.vm().contract_address().as_slice());
+        preimage.extend_from_slice(bidder.as_slice());
+        preimage.extend_from_slice(&U256::from(self.vm().block_timestamp()).to_be_bytes::<32>());
+        U256::from_be_bytes(crypto::keccak(preimage).0)
+    }
+
+    /// Version of the commitment preimage layout, so front ends can build the right hash
+    pub fn commitment_scheme(&self) -> U256 {
+        U256::from(COMMITMENT_SCHEME)
+    }
+
+    /// Whether revealing `bid` and `nonce` from the caller would match their stored commitment.
+    /// Lets wallets pre-flight a reveal before risking the forfeit on mismatch.
+    pub fn verify_reveal(&self, auction_id: U256, bid: U256, nonce: U256) -> bool {
+        let sender = self.vm().msg_sender();
+        let commitment = self.primary_commitment(auction_id, sender);
+        commitment != B256::ZERO && nonce != U256::ZERO && commitment_hash(sender, bid, nonce) == commitment
+    }
+
+    /// Number of distinct addresses that have committed
+    pub fn bidder_count(&self, auction_id: U256) -> U256 {
+        U256::from(self.auctions.get(auction_id).bidders.len())
+    }
+
+    /// Bidder at `index` in first-commit order (zero address if out of range)
+    pub fn bidder_at(&self, auction_id: U256, index: U256) -> Address {
+        self.auctions.get(auction_id).bidders.get(index).unwrap_or(Address::ZERO)
+    }
+
+    /// How far into `bidders` `sweep_forfeited()` has reached; equal to `bidder_count()` once done
+    pub fn swept_cursor(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).swept_cursor.get()
+    }
+
+    /// How far into `bidders` the `auto_credit_losers` pass has reached
+    pub fn credit_cursor(&self, auction_id: U256) -> U256 {
+        self.auctions.get(auction_id).credit_cursor.get()
+    }
+
+    /// Seconds left in the commit phase (zero once it has ended)
+    pub fn time_until_commit_end(&self, auction_id: U256) -> U256 {
+        let commit_end = self.auctions.get(auction_id).commit_end.get();
+        commit_end.saturating_sub(U256::from(self.vm().block_timestamp()))
+    }
+
+    /// Current dutch fallback price: `start_price` minus `decay_per_second` for every
+    /// second since finalize opened it, never below `floor_price`. Zero unless open.
+    pub fn dutch_price(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        let opened = auction.dutch_start.get();
+        if opened == U256::ZERO || auction.dutch_buyer.get() != Address::ZERO {
+            return U256::ZERO;
+        }
+        let elapsed = U256::from(self.vm().block_timestamp()).saturating_sub(opened);
+        let drop = auction.dutch_decay.get().saturating_mul(elapsed);
+        auction
+            .dutch_start_price
+            .get()
+            .saturating_sub(drop)
+            .max(auction.dutch_floor_price.get())
+    }
+
+    /// Who bought through the dutch fallback (zero if nobody has)
+    pub fn dutch_buyer(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id).dutch_buyer.get()
+    }
+
+    /// Seconds left in the reveal phase (zero once it has ended)
+    pub fn time_until_reveal_end(&self, auction_id: U256) -> U256 {
+        let reveal_end = self.auctions.get(auction_id).reveal_end.get();
+        reveal_end.saturating_sub(U256::from(self.vm().block_timestamp()))
+    }
+
+    /// Current auction phase:
+    /// 0 = not started, 1 = commit, 2 = reveal, 3 = ended but not finalized, 4 = finalized
+    pub fn phase(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        if auction.finalized.get() {
+            return U256::from(PHASE_FINALIZED);
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let phase = if now < auction.start_time.get() {
+            PHASE_NOT_STARTED
+        } else if now < auction.commit_end.get() {
+            PHASE_COMMIT
+        } else if now < auction.reveal_end.get() {
+            PHASE_REVEAL
+        } else {
+            PHASE_ENDED
+        };
+        U256::from(phase)
+    }
+
+    // Views for other contracts. None of them can revert: unknown auctions and undecided
+    // outcomes come back as zero values instead.
+
+    /// How the auction turned out:
+    /// 0 = unknown id or not decided yet, 1 = sold, 2 = closed unsold, 3 = cancelled.
+    /// A dutch fallback purchase counts as sold.
+    pub fn safe_outcome(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        let outcome = if auction.seller.get() == Address::ZERO || !auction.finalized.get() {
+            OUTCOME_PENDING
+        } else if auction.cancelled.get() {
+            OUTCOME_CANCELLED
+        } else if auction.sold.get() || auction.dutch_buyer.get() != Address::ZERO {
+            OUTCOME_SOLD
+        } else {
+            OUTCOME_UNSOLD
+        };
+        U256::from(outcome)
+    }
+
+    /// Who receives the NFT: the sealed-bid winner or the dutch buyer. Zero until the
+    /// auction is finalized as sold, whoever currently leads the reveals.
+    pub fn safe_winner(&self, auction_id: U256) -> Address {
+        let auction = self.auctions.get(auction_id);
+        if !auction.finalized.get() || auction.cancelled.get() {
+            return Address::ZERO;
+        }
+        if auction.sold.get() {
+            auction.highest_bidder.get()
+        } else {
+            auction.dutch_buyer.get()
+        }
+    }
+
+    /// Winning sealed bid once the auction is finalized as sold; zero otherwise, including
+    /// dutch fallback sales
+    pub fn safe_winning_bid(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        if !auction.finalized.get() || auction.cancelled.get() || !auction.sold.get() {
+            return U256::ZERO;
+        }
+        auction.highest_bid.get()
+    }
+}
+
+impl SealedBidAuction {
+    /// Take the reentrancy lock. An `Err` from the entry point reverts the whole call,
+    /// so only successful paths need to release it via `exit()`.
+    fn enter(&mut self) -> Result<(), SealedBidError> {
+        if self.locked.get() {
+            return Err(SealedBidError::Reentrancy(Reentrancy {}));
+        }
+        self.locked.set(true);
+        Ok(())
+    }
+
+    fn exit(&mut self) {
+        self.locked.set(false);
+    }
+
+    fn only_owner(&self) -> Result<(), SealedBidError> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err(SealedBidError::NotOwner(NotOwner {}));
+        }
+        Ok(())
+    }
+
+    fn require_exists(&self, auction_id: U256) -> Result<(), SealedBidError> {
+        if self.auctions.get(auction_id).seller.get() == Address::ZERO {
+            return Err(SealedBidError::AuctionNotFound(AuctionNotFound {}));
+        }
+        Ok(())
+    }
+
+    // Phase boundaries: the commit phase runs while `now < commit_end`, the reveal phase
+    // while `commit_end <= now < reveal_end`, and the auction has ended once `now >= reveal_end`.
+    // Each timestamp therefore belongs to exactly one phase, matching `phase()`.
+
+    fn require_commit_phase(&self, auction_id: U256) -> Result<(), SealedBidError> {
+        let now = U256::from(self.vm().block_timestamp());
+        if now >= self.auctions.get(auction_id).commit_end.get() {
+            return Err(SealedBidError::CommitPhaseOver(CommitPhaseOver {}));
+        }
+        Ok(())
+    }
+
+    fn require_reveal_phase(&self, auction_id: U256) -> Result<(), SealedBidError> {
+        let auction = self.auctions.get(auction_id);
+        let now = U256::from(self.vm().block_timestamp());
+        if now < auction.commit_end.get() {
+            return Err(SealedBidError::RevealNotOpen(RevealNotOpen {}));
+        }
+        if now >= auction.reveal_end.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        Ok(())
+    }
+
+    fn require_ended(&self, auction_id: U256) -> Result<(), SealedBidError> {
+        let now = U256::from(self.vm().block_timestamp());
+        if now < self.auctions.get(auction_id).reveal_end.get() {
+            return Err(SealedBidError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+        Ok(())
+    }
+
+    fn require_not_paused(&self, auction_id: U256) -> Result<(), SealedBidError> {
+        if self.auctions.get(auction_id).paused.get() {
+            return Err(SealedBidError::Paused(Paused {}));
+        }
+        Ok(())
+    }
+
+    /// Reveal `who`'s primary sealed bid, through the per-index path if they hold several
+    fn reveal_primary(&mut self, auction_id: U256, who: Address, bid: U256, nonce: U256) -> Result<(), SealedBidError> {
+        if self.auctions.get(auction_id).commitments.get(who).len() > 1 {
+            return self.record_reveal_at(auction_id, who, U256::ZERO, bid, nonce);
+        }
+        self.record_reveal(auction_id, who, bid, nonce)
+    }
+
+    /// Check a single-bid holder's reveal against their commitment and update the standings
+    fn record_reveal(&mut self, auction_id: U256, sender: Address, bid: U256, nonce: U256) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+
+        self.require_reveal_phase(auction_id)?;
+
+        // without a nonce a low bid could be brute-forced from its commitment
+        if nonce == U256::ZERO {
+            return Err(SealedBidError::WeakNonce(WeakNonce {}));
+        }
+
+        let mut auction = self.auctions.setter(auction_id);
+
+        // One reveal per bidder, whatever its outcome. This also stops the current leader
+        // from revealing again to raise a bid others have already seen.
+        if auction.revealed.get(sender) {
+            return Err(SealedBidError::AlreadyRevealed(AlreadyRevealed {}));
+        }
+
+        let commitment = auction.commitments.get(sender).get(0).unwrap_or(B256::ZERO);
+        if commitment == B256::ZERO {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+
+        // Recompute keccak256(sender || bid || nonce) and compare.
+        let matches = commitment == commitment_hash(sender, bid, nonce);
+
+        if !matches && auction.forgiving_reveal.get() {
+            // forgiving mode: nothing needs recording, so revert and let the bidder retry before reveal_end
+            return Err(SealedBidError::CommitmentMismatch(CommitmentMismatch {}));
+        }
+
+        if !matches {
+            // invalid reveal: mark revealed so attacker cannot retry
+            auction.revealed.setter(sender).set(true);
+            log(auction.vm(), RevealFailed {
+                auctionId: auction_id,
+                bidder: sender,
+            });
+            if !auction.forfeit_on_invalid.get() {
+                // lenient policy: the bidder just doesn't win and gets the deposit back
+                let depos = auction.deposits.get(sender);
+                auction.deposits.setter(sender).set(U256::ZERO);
+                escrow_out(&mut self.total_deposits, &auction, depos);
+                let prev = auction.refunds.get(sender);
+                auction.refunds.setter(sender).set(prev + depos);
+                return Ok(());
+            }
+            // deposit remains in contract (forfeited)
+            auction.forfeited.setter(sender).set(true);
+            log(auction.vm(), DepositForfeited {
+                auctionId: auction_id,
+                bidder: sender,
+                amount: auction.deposits.get(sender),
+                reason: FORFEIT_HASH_MISMATCH,
+            });
+            // returning an error here would revert the forfeit, so the call succeeds
+            return Ok(());
+        }
+
+        // valid reveal
+        auction.revealed.setter(sender).set(true);
+        auction.revealed_mask.setter(sender).set(U256::from(1));
+
+        // get deposit for this sender
+        let depos = auction.deposits.get(sender);
+
+        if depos < auction.min_deposit.get() {
+            // insufficient deposit -> treat as invalid; succeed so the outcome sticks
+            if !auction.forfeit_on_invalid.get() {
+                auction.deposits.setter(sender).set(U256::ZERO);
+                escrow_out(&mut self.total_deposits, &auction, depos);
+                let prev = auction.refunds.get(sender);
+                auction.refunds.setter(sender).set(prev + depos);
+                return Ok(());
+            }
+            auction.forfeited.setter(sender).set(true);
+            log(self.vm(), DepositForfeited {
+                auctionId: auction_id,
+                bidder: sender,
+                amount: depos,
+                reason: FORFEIT_INSUFFICIENT_DEPOSIT,
+            });
+            return Ok(());
+        }
+
+        self.compete(auction_id, sender, 0, bid, depos, true)
+    }
+
+    /// Reveal sealed bid `index` of a bidder holding several (`multi_bid`). Each one competes
+    /// on its own, backed only by the deposit paid with it. A mismatch always reverts, since
+    /// leaving a bid unopened is allowed anyway; the bidder counts as revealed once every
+    /// bid is open, and until then their unopened bids are treated as never revealed.
+    fn record_reveal_at(
+        &mut self,
+        auction_id: U256,
+        sender: Address,
+        index: U256,
+        bid: U256,
+        nonce: U256,
+    ) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_reveal_phase(auction_id)?;
+
+        if nonce == U256::ZERO {
+            return Err(SealedBidError::WeakNonce(WeakNonce {}));
+        }
+
+        let auction = self.auctions.get(auction_id);
+        if auction.revealed.get(sender) {
+            return Err(SealedBidError::AlreadyRevealed(AlreadyRevealed {}));
+        }
+        let commitments = auction.commitments.get(sender);
+        let count = commitments.len();
+        if count == 0 {
+            return Err(SealedBidError::NoCommitmentFound(NoCommitmentFound {}));
+        }
+        if index >= U256::from(count) {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        let index = index.to::<usize>();
+        let bit = U256::from(1) << index;
+        let mask = auction.revealed_mask.get(sender);
+        if mask & bit != U256::ZERO {
+            return Err(SealedBidError::AlreadyRevealed(AlreadyRevealed {}));
+        }
+        if commitments.get(index) != Some(commitment_hash(sender, bid, nonce)) {
+            return Err(SealedBidError::CommitmentMismatch(CommitmentMismatch {}));
+        }
+
+        let depos = self.bid_deposit(auction_id, sender, index);
+        let mut auction = self.auctions.setter(auction_id);
+        auction.revealed_mask.setter(sender).set(mask | bit);
+        if mask | bit == (U256::from(1) << count) - U256::from(1) {
+            auction.revealed.setter(sender).set(true);
+        }
+
+        self.compete(auction_id, sender, index, bid, depos, mask == U256::ZERO)
+    }
+
+    /// Let `sender`'s validly revealed `bid` (sealed bid `index`, backed by `depos`) compete
+    /// for the lead. A bid that is capped, under-collateralized, below the reserve or beaten
+    /// only has its own deposit credited back to `refunds`. `first_reveal` is whether this
+    /// is the bidder's first valid reveal, which is what `reveal_count` counts.
+    fn compete(
+        &mut self,
+        auction_id: U256,
+        sender: Address,
+        index: usize,
+        bid: U256,
+        depos: U256,
+        first_reveal: bool,
+    ) -> Result<(), SealedBidError> {
+        let auction = self.auctions.get(auction_id);
+
+        // Bids above the cap are rejected outright rather than clamped: they don't count
+        // as a reveal and the deposit is refunded, since the bidder did reveal honestly.
+        let max_bid = auction.max_bid.get();
+        if max_bid != U256::ZERO && bid > max_bid {
+            self.release_bid_deposit(auction_id, sender, index);
+            log(self.vm(), BidRejectedCap {
+                auctionId: auction_id,
+                bidder: sender,
+                bid,
+                maxBid: max_bid,
+            });
+            return Ok(());
+        }
+
+        // Same treatment for a bid the deposit doesn't cover by `deposit_bps`: the reveal was
+        // honest, so the deposit is refunded rather than forfeited, but the bid is ignored.
+        let deposit_bps = auction.deposit_bps.get();
+        // a product too large for 256 bits can only come from an absurd bid, so it counts as uncovered
+        let covered = depos.saturating_mul(U256::from(BPS_DENOMINATOR));
+        if depos < auction.min_deposit.get() || bid.checked_mul(deposit_bps).is_none_or(|required| covered < required) {
+            self.release_bid_deposit(auction_id, sender, index);
+            log(self.vm(), BidRejectedDeposit {
+                auctionId: auction_id,
+                bidder: sender,
+                bid,
+                deposit: depos,
+            });
+            return Ok(());
+        }
+
+        // ordered mode: each competing bid may not exceed the one before it, so the first
+        // reveal is the highest; a bidder who missed their turn can no longer reveal.
+        // Bids below the reserve can't win and are left out, so a free low reveal can't
+        // lock everyone else out.
+        let mut auction = self.auctions.setter(auction_id);
+        if bid >= auction.reserve_price.get() {
+            if auction.ordered_reveal.get()
+                && auction.highest_bidder.get() != Address::ZERO
+                && bid > auction.last_revealed_bid.get()
+            {
+                return Err(SealedBidError::RevealOrderViolation(RevealOrderViolation {}));
+            }
+            auction.last_revealed_bid.set(bid);
+        }
+
+        if bid > auction.revealed_bids.get(sender) {
+            auction.revealed_bids.setter(sender).set(bid);
+        }
+        if first_reveal {
+            let reveal_count = auction.reveal_count.get();
+            auction.reveal_count.set(reveal_count + U256::from(1));
+        }
+        log(auction.vm(), BidRevealed {
+            auctionId: auction_id,
+            bidder: sender,
+            bid,
+        });
+
+        // A bid below the reserve can never win, so it is refunded straight away and
+        // never touches highest_bid.
+        if bid < auction.reserve_price.get() {
+            self.release_bid_deposit(auction_id, sender, index);
+            return Ok(());
+        }
+
+        // Accept the revealed bid only if it clears the current highest by `min_increment`.
+        // Anything else is a losing bid.
+        // Ties go to whoever committed first (then whoever revealed first); the loser is refunded.
+        if self.outbids_highest(auction_id, sender, bid) {
+            // The previous highest bidder is outbid. Bids are only escrowed by `settle()`
+            // after the reveal phase, so all they have locked is the deposit of the leading bid.
+            let prev_high = self.auctions.get(auction_id).highest_bidder.get();
+            if prev_high != Address::ZERO {
+                let keep = if prev_high == sender { Some(index) } else { None };
+                self.release_revealed(auction_id, prev_high, keep);
+            }
+
+            // set new highest (and keep this bidder's deposit in contract until finalize or refund)
+            let mut auction = self.auctions.setter(auction_id);
+            let prev_bid = auction.highest_bid.get();
+            auction.second_highest_bid.set(prev_bid);
+            auction.second_highest_bidder.set(prev_high);
+            auction.highest_bid.set(bid);
+            auction.highest_bidder.set(sender);
+
+            log(self.vm(), NewHighestBid {
+                auctionId: auction_id,
+                bidder: sender,
+                bid,
+                previousBidder: prev_high,
+            });
+            // an equal bid only takes the lead by committing earlier, so the displaced leader lost a tie
+            if prev_high != Address::ZERO && bid == prev_bid {
+                log(self.vm(), TieRejected {
+                    auctionId: auction_id,
+                    bidder: prev_high,
+                    bid,
+                });
+            }
+
+            self.maybe_extend_reveal(auction_id, U256::from(self.vm().block_timestamp()));
+
+            // For the current revealer, we reduce their deposit by nothing now; funds stay locked
+            // actual funds transfer to seller happens in finalize
+        } else {
+            let mut auction = self.auctions.setter(auction_id);
+            // Not a winning bid: the deposit (the only thing escrowed) becomes refundable.
+            if bid == auction.highest_bid.get() && auction.highest_bidder.get() != Address::ZERO {
+                log(auction.vm(), TieRejected {
+                    auctionId: auction_id,
+                    bidder: sender,
+                    bid,
+                });
+            }
+            if bid > auction.second_highest_bid.get() {
+                auction.second_highest_bid.set(bid);
+                auction.second_highest_bidder.set(sender);
+            }
+            self.release_bid_deposit(auction_id, sender, index);
+        }
+
+        Ok(())
+    }
+
+    /// Deposit backing `who`'s sealed bid at `index`. Extra bids carry what was paid with
+    /// `commit_additional`; the primary bid has the rest of the deposit.
+    fn bid_deposit(&self, auction_id: U256, who: Address, index: usize) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        let extras = auction.bid_deposits.get(who);
+        if index > 0 {
+            return extras.get(index - 1).unwrap_or(U256::ZERO);
+        }
+        let mut extra_total = U256::ZERO;
+        for i in 0..extras.len() {
+            extra_total += extras.get(i).unwrap_or(U256::ZERO);
+        }
+        auction.deposits.get(who) - extra_total
+    }
+
+    /// Credit the deposit backing `who`'s sealed bid at `index` to their refund balance
+    fn release_bid_deposit(&mut self, auction_id: U256, who: Address, index: usize) {
+        let amount = self.bid_deposit(auction_id, who, index);
+        let mut auction = self.auctions.setter(auction_id);
+        if index > 0 {
+            if let Some(mut slot) = auction.bid_deposits.setter(who).setter(index - 1) {
+                slot.set(U256::ZERO);
+            }
+        }
+        let deposit = auction.deposits.get(who);
+        auction.deposits.setter(who).set(deposit - amount);
+        escrow_out(&mut self.total_deposits, &auction, amount);
+        let prev = auction.refunds.get(who);
+        auction.refunds.setter(who).set(prev + amount);
+    }
+
+    /// Credit back the deposits of `who`'s revealed bids other than `keep`. Losing bids were
+    /// already released when revealed, so this frees the one that led.
+    fn release_revealed(&mut self, auction_id: U256, who: Address, keep: Option<usize>) {
+        let auction = self.auctions.get(auction_id);
+        let mask = auction.revealed_mask.get(who);
+        let count = auction.commitments.get(who).len();
+        for i in 0..count {
+            if Some(i) != keep && mask & (U256::from(1) << i) != U256::ZERO {
+                self.release_bid_deposit(auction_id, who, i);
+            }
+        }
+    }
+
+    /// Signer of the 65-byte `signature` (r || s || v) over `digest`, via the ecrecover precompile
+    fn recover_signer(&mut self, digest: B256, signature: &[u8]) -> Option<Address> {
+        if signature.len() != 65 {
+            return None;
+        }
+        let v = match signature[64] {
+            0 | 1 => signature[64] + 27,
+            v => v,
+        };
+        let mut input: Vec<u8> = Vec::new();
+        input.extend_from_slice(digest.as_slice());
+        input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+        input.extend_from_slice(&signature[..64]);
+        // the precompile returns nothing for an invalid signature
+        let output = self.vm().static_call(&calls::context::Call::new(), ECRECOVER, &input).ok()?;
+        if output.len() != 32 {
+            return None;
+        }
+        Some(Address::from_slice(&output[12..32]))
+    }
+
+    /// Store `bidder`'s commitment and add the deposit paid by `payer`.
+    /// ERC20 auctions pull `token_amount` if given, else `min_deposit` on the first commit.
+    fn record_commit(
+        &mut self,
+        auction_id: U256,
+        payer: Address,
+        bidder: Address,
+        commitment: B256,
+        proof: &[B256],
+        token_amount: Option<U256>,
+    ) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+        self.require_not_paused(auction_id)?;
+        self.require_commit_phase(auction_id)?;
+
+        let auction = self.auctions.get(auction_id);
+        let now = U256::from(self.vm().block_timestamp());
+        if auction.commit_end.get() - now < auction.commit_buffer.get() {
+            return Err(SealedBidError::CommitPhaseOver(CommitPhaseOver {}));
+        }
+
+        if commitment == B256::ZERO {
+            return Err(SealedBidError::EmptyCommitment(EmptyCommitment {}));
+        }
+
+        // only the bidder may replace their own commitment; otherwise anyone could swap
+        // in junk for free and make the real reveal fail
+        if payer != bidder && self.primary_commitment(auction_id, bidder) != B256::ZERO {
+            return Err(SealedBidError::AlreadyCommitted(AlreadyCommitted {}));
+        }
+
+        // a Merkle root makes the auction private too; either allowlist admits the bidder
+        let allow_root = auction.allow_root.get();
+        if (auction.private_auction.get() || allow_root != B256::ZERO)
+            && !auction.allowed.get(bidder)
+            && !(allow_root != B256::ZERO && merkle_verify(allow_root, bidder, proof))
+        {
+            return Err(SealedBidError::NotAllowlisted(NotAllowlisted {}));
+        }
+
+        // keeps every loop over `bidders` within the block gas limit
+        if !auction.has_committed.get(bidder) && U256::from(auction.bidders.len()) >= self.max_bidders() {
+            return Err(SealedBidError::BidderLimitReached(BidderLimitReached {}));
+        }
+
+        let payment_token = auction.payment_token.get();
+        // ETH sent to an ERC20 auction isn't tracked anywhere and would be stuck
+        if payment_token != Address::ZERO && self.vm().msg_value() > U256::ZERO {
+            return Err(SealedBidError::UnexpectedEther(UnexpectedEther {}));
+        }
+        let min_deposit = auction.min_deposit.get();
+        let prev_deposit = auction.deposits.get(bidder);
+        let value = if payment_token == Address::ZERO {
+            self.vm().msg_value()
+        } else if let Some(amount) = token_amount {
+            if amount > U256::ZERO {
+                self.pull_funds(payment_token, payer, amount)?;
+            }
+            amount
+        } else if prev_deposit == U256::ZERO {
+            self.pull_funds(payment_token, payer, min_deposit)?;
+            min_deposit
+        } else {
+            U256::ZERO
+        };
+        if value < min_deposit && prev_deposit == U256::ZERO {
+            // If the bidder hasn't deposited before, require at least min_deposit
+            return Err(SealedBidError::NoDeposit(NoDeposit {}));
+        }
+
+        // store/overwrite commitment
+        self.check_commitment_unique(auction_id, bidder, commitment, 0)?;
+        self.set_primary_commitment(auction_id, bidder, commitment);
+        let mut auction = self.auctions.setter(auction_id);
+
+        // record first-time bidders for enumeration
+        if !auction.has_committed.get(bidder) {
+            auction.has_committed.setter(bidder).set(true);
+            let now = U256::from(auction.vm().block_timestamp());
+            auction.commit_time.setter(bidder).set(now);
+            auction.bidders.push(bidder);
+            let commit_count = auction.commit_count.get();
+            auction.commit_count.set(commit_count + U256::from(1));
+        }
+
+        // accumulate deposits
+        let total_deposit = prev_deposit + value;
+        if value > U256::ZERO {
+            auction.deposits.setter(bidder).set(total_deposit);
+            escrow_in(&mut self.total_deposits, &auction, value);
+        }
+
+        // only the hash is logged; the bid itself stays sealed
+        log(self.vm(), BidCommitted {
+            auctionId: auction_id,
+            bidder,
+            commitment,
+            totalDeposit: total_deposit,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out `who`'s whole refund balance to `to`
+    fn pay_refund(&mut self, auction_id: U256, who: Address, to: Address) -> Result<(), SealedBidError> {
+        let amount = self.auctions.get(auction_id).refunds.get(who);
+        if amount == U256::ZERO {
+            return Err(SealedBidError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+
+        // zero out before transfer (checks-effects-interactions)
+        self.auctions.setter(auction_id).refunds.setter(who).set(U256::ZERO);
+
+        if !self.send_funds(auction_id, to, amount) {
+            // restore on failure
+            let mut auction = self.auctions.setter(auction_id);
+            let prev = auction.refunds.get(who);
+            auction.refunds.setter(who).set(prev + amount);
+            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+        }
+
+        log(self.vm(), RefundWithdrawn {
+            auctionId: auction_id,
+            who,
+            amount,
+        });
+        Ok(())
+    }
+
+    fn set_allowed(&mut self, auction_id: U256, accounts: Vec<Address>, allowed: bool) -> Result<(), SealedBidError> {
+        self.require_exists(auction_id)?;
+
+        let mut auction = self.auctions.setter(auction_id);
+        if auction.vm().msg_sender() != auction.seller.get() {
+            return Err(SealedBidError::OnlySeller(OnlySeller {}));
+        }
+        for account in accounts {
+            auction.allowed.setter(account).set(allowed);
+            if allowed {
+                log(auction.vm(), AllowlistAdded { auctionId: auction_id, bidder: account });
+            } else {
+                log(auction.vm(), AllowlistRemoved { auctionId: auction_id, bidder: account });
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark every forfeited deposit (never revealed, or revealed a mismatching hash) among
+    /// `bidders[from..to]` as swept and return their sum. The winner's escrow is never included.
+    fn collect_forfeited(&mut self, auction_id: U256, from: usize, to: usize) -> U256 {
+        let mut auction = self.auctions.setter(auction_id);
+        let winner = auction.highest_bidder.get();
+        let mut total = U256::ZERO;
+        for i in from..to {
+            let who = auction.bidders.get(i).unwrap_or(Address::ZERO);
+            if who == winner || auction.swept.get(who) {
+                continue;
+            }
+            // valid revealers were credited their refund during reveal
+            if !deposit_forfeited(&auction, who) {
+                continue;
+            }
+            let deposit = auction.deposits.get(who);
+            if deposit == U256::ZERO {
+                continue;
+            }
+            auction.swept.setter(who).set(true);
+            auction.deposits.setter(who).set(U256::ZERO);
+            escrow_out(&mut self.total_deposits, &auction, deposit);
+            total += deposit;
+            // bad reveals already reported their forfeit when they happened
+            if !auction.revealed.get(who) {
+                log(auction.vm(), DepositForfeited {
+                    auctionId: auction_id,
+                    bidder: who,
+                    amount: deposit,
+                    reason: FORFEIT_NEVER_REVEALED,
+                });
+            }
+        }
+        total
+    }
+
+    /// Credit every bidder's remaining deposit to their refund balance
+    fn refund_deposits(&mut self, auction_id: U256) {
+        let mut auction = self.auctions.setter(auction_id);
+        for i in 0..auction.bidders.len() {
+            let who = auction.bidders.get(i).unwrap_or(Address::ZERO);
+            let deposit = auction.deposits.get(who);
+            if deposit == U256::ZERO {
+                continue;
+            }
+            auction.deposits.setter(who).set(U256::ZERO);
+            escrow_out(&mut self.total_deposits, &auction, deposit);
+            let prev = auction.refunds.get(who);
+            auction.refunds.setter(who).set(prev + deposit);
+        }
+    }
+
+    /// Credit the deposits of losing bidders in `bidders[from..to]` that are still escrowed
+    /// after finalize. Valid revealers always get theirs; non-revealers only when
+    /// `forfeit_on_invalid` is clear, otherwise they are left for `sweep_forfeited`. The
+    /// winner's escrow was already settled, so its deposit is zero here.
+    fn credit_losers_range(&mut self, auction_id: U256, from: usize, to: usize) {
+        let mut auction = self.auctions.setter(auction_id);
+        for i in from..to {
+            let who = auction.bidders.get(i).unwrap_or(Address::ZERO);
+            if auction.swept.get(who) || deposit_forfeited(&auction, who) {
+                continue;
+            }
+            let deposit = auction.deposits.get(who);
+            if deposit == U256::ZERO {
+                continue;
+            }
+            auction.deposits.setter(who).set(U256::ZERO);
+            escrow_out(&mut self.total_deposits, &auction, deposit);
+            let prev = auction.refunds.get(who);
+            auction.refunds.setter(who).set(prev + deposit);
+        }
+    }
+
+    /// Run finalize's per-bidder pass over `bidders[from..to]`: credit losers when
+    /// `auto_credit_losers` is set, and move forfeited deposits to the seller's refunds
+    /// when `nonrevealed_penalty_to_seller` is. Both cursors only move forward.
+    fn settle_losers_range(&mut self, auction_id: U256, from: usize, to: usize) {
+        let auction = self.auctions.get(auction_id);
+        let auto_credit = auction.auto_credit_losers.get();
+        let penalty_to_seller = auction.nonrevealed_penalty_to_seller.get();
+        if auto_credit {
+            self.credit_losers_range(auction_id, from, to);
+            let mut auction = self.auctions.setter(auction_id);
+            if U256::from(to) > auction.credit_cursor.get() {
+                auction.credit_cursor.set(U256::from(to));
+            }
+        }
+        if penalty_to_seller {
+            let penalty = self.collect_forfeited(auction_id, from, to);
+            let mut auction = self.auctions.setter(auction_id);
+            if U256::from(to) > auction.swept_cursor.get() {
+                auction.swept_cursor.set(U256::from(to));
+            }
+            if penalty > U256::ZERO {
+                let seller = auction.seller.get();
+                let prev = auction.refunds.get(seller);
+                auction.refunds.setter(seller).set(prev + penalty);
+            }
+        }
+    }
+
+    /// Clamp a `(start, count)` page to the auction's `bidders` list. The list no longer
+    /// changes once the commit phase is over, so pages stay stable between calls.
+    fn bidder_range(&self, auction_id: U256, start: U256, count: U256) -> Result<(usize, usize), SealedBidError> {
+        let len = U256::from(self.auctions.get(auction_id).bidders.len());
+        if count == U256::ZERO || start >= len {
+            return Err(SealedBidError::InvalidAmount(InvalidAmount {}));
+        }
+        let end = start.saturating_add(count).min(len);
+        Ok((start.to::<usize>(), end.to::<usize>()))
+    }
+
+    /// `who`'s first sealed bid, the one `commit`, `recommit` and `reveal` work on (zero if none)
+    fn primary_commitment(&self, auction_id: U256, who: Address) -> B256 {
+        self.auctions.get(auction_id).commitments.get(who).get(0).unwrap_or(B256::ZERO)
+    }
+
+    /// Reject `commitment` if another address committed it first, or if `who` already holds
+    /// it in a slot other than `slot`, the one it is about to fill. Otherwise the hash is
+    /// bound to `who` for the rest of the auction.
+    fn check_commitment_unique(&mut self, auction_id: U256, who: Address, commitment: B256, slot: usize) -> Result<(), SealedBidError> {
+        let mut auction = self.auctions.setter(auction_id);
+        let owner = auction.commitment_owner.get(commitment);
+        if owner != Address::ZERO && owner != who {
+            return Err(SealedBidError::CommitmentAlreadyUsed(CommitmentAlreadyUsed {}));
+        }
+        let commitments = auction.commitments.get(who);
+        for i in 0..commitments.len() {
+            if i != slot && commitments.get(i) == Some(commitment) {
+                return Err(SealedBidError::CommitmentAlreadyUsed(CommitmentAlreadyUsed {}));
+            }
+        }
+        auction.commitment_owner.setter(commitment).set(who);
+        Ok(())
+    }
+
+    /// Store `commitment` as `who`'s primary sealed bid, keeping any extra ones
+    fn set_primary_commitment(&mut self, auction_id: U256, who: Address, commitment: B256) {
+        let mut auction = self.auctions.setter(auction_id);
+        let mut commitments = auction.commitments.setter(who);
+        if let Some(mut slot) = commitments.setter(0) {
+            slot.set(commitment);
+        } else {
+            commitments.push(commitment);
+        }
+    }
+
+    /// Pay out the seller's bond through `refunds`: back to the seller, or when `forfeit`
+    /// is set split equally among the bidders still committed (the first one also gets
+    /// the rounding remainder). Runs at most once, since the bond is zeroed.
+    fn release_bond(&mut self, auction_id: U256, forfeit: bool) {
+        let mut auction = self.auctions.setter(auction_id);
+        let bond = auction.seller_bond.get();
+        if bond == U256::ZERO {
+            return;
+        }
+        auction.seller_bond.set(U256::ZERO);
+
+        let len = auction.bidders.len();
+        if !forfeit || len == 0 {
+            let seller = auction.seller.get();
+            let prev = auction.refunds.get(seller);
+            auction.refunds.setter(seller).set(prev + bond);
+            return;
+        }
+
+        let share = bond / U256::from(len);
+        let remainder = bond - share * U256::from(len);
+        for i in 0..len {
+            let who = auction.bidders.get(i).unwrap_or(Address::ZERO);
+            let credit = if i == 0 { share + remainder } else { share };
+            let prev = auction.refunds.get(who);
+            auction.refunds.setter(who).set(prev + credit);
+        }
+        log(self.vm(), SellerBondForfeited {
+            auctionId: auction_id,
+            amount: bond,
+            bidders: U256::from(len),
+        });
+    }
+
+    /// Swap-remove `who` from the auction's `bidders` list
+    fn remove_bidder(&mut self, auction_id: U256, who: Address) {
+        let mut auction = self.auctions.setter(auction_id);
+        let len = auction.bidders.len();
+        for i in 0..len {
+            if auction.bidders.get(i) == Some(who) {
+                let last = auction.bidders.get(len - 1).unwrap_or(Address::ZERO);
+                if let Some(mut slot) = auction.bidders.setter(i) {
+                    slot.set(last);
+                }
+                auction.bidders.pop();
+                return;
+            }
+        }
+    }
+
+    /// Verifies seller owns NFT and contract is approved to transfer it
+    fn verify_nft_authorization(&mut self, auction_id: U256, seller: Address) -> Result<(), SealedBidError> {
+        if self.auctions.get(auction_id).is_erc1155.get() {
+            return self.verify_erc1155_authorization(auction_id, seller);
+        }
+
+        let nft_contract = self.auctions.get(auction_id).nft_contract.get();
+        let token_ids = self.bundle(auction_id);
+
+        // owner_of
+        for &token_id in &token_ids {
+            let owner_res = self.view_call(nft_contract, IERC721::ownerOfCall { token_id });
+            match owner_res {
+                Some(owner) => {
+                    if owner._0 != seller {
+                        return Err(SealedBidError::NotNFTOwner(NotNFTOwner {}));
+                    }
+                }
+                None => return Err(SealedBidError::NFTTransferFailed(NFTTransferFailed {})),
+            }
+        }
+
+        // collection-wide approval is the common case; only query the per-token approval without it
+        let contract_address = self.vm().contract_address();
+        let approved_for_all_res = self.view_call(
+            nft_contract,
+            IERC721::isApprovedForAllCall { owner: seller, operator: contract_address },
+        );
+
+        let is_approved_for_all = approved_for_all_res.is_some_and(|res| res._0);
+        if is_approved_for_all {
+            return Ok(());
+        }
+
+        for &token_id in &token_ids {
+            let approved_res = self.view_call(nft_contract, IERC721::getApprovedCall { token_id });
+            let is_approved = match approved_res {
+                Some(approved) => approved._0 == contract_address,
+                None => false,
+            };
+
+            if !is_approved {
+                return Err(SealedBidError::NotApproved(NotApproved {}));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `seller` still holds every NFT in the bundle (or every ERC1155 lot).
+    /// A failing ownership query counts as no longer owned.
+    fn seller_still_owns(&self, auction_id: U256, seller: Address) -> bool {
+        let auction = self.auctions.get(auction_id);
+        let nft_address = auction.nft_contract.get();
+        let is_erc1155 = auction.is_erc1155.get();
+        let amount = auction.amount.get();
+        for token_id in self.bundle(auction_id) {
+            let owns = if is_erc1155 {
+                let res = self.view_call(nft_address, IERC1155::balanceOfCall { account: seller, id: token_id });
+                matches!(res, Some(balance) if balance._0 >= amount)
+            } else {
+                let res = self.view_call(nft_address, IERC721::ownerOfCall { token_id });
+                matches!(res, Some(owner) if owner._0 == seller)
+            };
+            if !owns {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Verifies seller holds at least `amount` of every ERC1155 id and has approved this contract
+    fn verify_erc1155_authorization(&mut self, auction_id: U256, seller: Address) -> Result<(), SealedBidError> {
+        let auction = self.auctions.get(auction_id);
+        let token = auction.nft_contract.get();
+        let amount = auction.amount.get();
+
+        for token_id in self.bundle(auction_id) {
+            let balance_res = self.view_call(token, IERC1155::balanceOfCall { account: seller, id: token_id });
+            match balance_res {
+                Some(balance) => {
+                    if balance._0 < amount {
+                        return Err(SealedBidError::NotNFTOwner(NotNFTOwner {}));
+                    }
+                }
+                None => return Err(SealedBidError::NFTTransferFailed(NFTTransferFailed {})),
+            }
+        }
+
+        let operator = self.vm().contract_address();
+        let approved_res = self.view_call(token, IERC1155::isApprovedForAllCall { account: seller, operator });
+        if !approved_res.is_some_and(|res| res._0) {
+            return Err(SealedBidError::NotApproved(NotApproved {}));
+        }
+
+        Ok(())
+    }
+
+    /// Push `reveal_end` out by the anti-snipe window when a new highest bid lands
+    /// inside the final window, up to `MAX_REVEAL_EXTENSIONS` times.
+    fn maybe_extend_reveal(&mut self, auction_id: U256, now: U256) {
+        let mut auction = self.auctions.setter(auction_id);
+        let window = auction.anti_snipe_window.get();
+        if window == U256::ZERO {
+            return;
+        }
+        let extensions = auction.reveal_extensions.get();
+        if extensions >= U256::from(MAX_REVEAL_EXTENSIONS) {
+            return;
+        }
+        let reveal_end = auction.reveal_end.get();
+        if reveal_end - now < window {
+            let new_end = reveal_end + window;
+            auction.reveal_end.set(new_end);
+            auction.reveal_extensions.set(extensions + U256::from(1));
+            log(self.vm(), RevealExtended {
+                auctionId: auction_id,
+                newRevealEnd: new_end,
+            });
+        }
+    }
+
+    /// Pay a sale at `price` out of the contract: protocol fee and creator royalty, then the
+    /// rest is credited to the beneficiary's `seller_proceeds` and recorded as `settled_price`.
+    fn pay_out(&mut self, auction_id: U256, price: U256) -> Result<(), SealedBidError> {
+        let auction = self.auctions.get(auction_id);
+        let fee_recipient = auction.fee_recipient.get();
+
+        let (fee, royalty_receiver, royalty, proceeds) = self.split_sale(auction_id, price);
+        if fee > U256::ZERO {
+            self.transfer_payment(auction_id, fee_recipient, fee)?;
+            log(self.vm(), FeeCollected {
+                auctionId: auction_id,
+                recipient: fee_recipient,
+                amount: fee,
+            });
+        }
+        if royalty > U256::ZERO {
+            self.transfer_payment(auction_id, royalty_receiver, royalty)?;
+        }
+        // credited rather than pushed, so a beneficiary that rejects payment can't block settlement
+        let mut auction = self.auctions.setter(auction_id);
+        let owed = auction.seller_proceeds.get();
+        auction.seller_proceeds.set(owed + proceeds);
+        auction.settled_price.set(proceeds);
+        Ok(())
+    }
+
+    /// How a sale at `price` is divided: (fee, royalty receiver, royalty, seller proceeds)
+    fn split_sale(&self, auction_id: U256, price: U256) -> (U256, Address, U256, U256) {
+        let fee_bps = self.auctions.get(auction_id).fee_bps.get();
+        // Compute amount to send to seller, net of protocol fee and creator royalty
+        let fee = price * fee_bps / U256::from(BPS_DENOMINATOR);
+        let (royalty_receiver, royalty) = self.royalty_for(auction_id, price, price - fee);
+        (fee, royalty_receiver, royalty, price - fee - royalty)
+    }
+
+    /// ERC2981 royalty owed on a sale at `price`, capped at `cap`.
+    /// NFTs that don't implement ERC2981 (the call reverts) pay no royalty.
+    fn royalty_for(&self, auction_id: U256, price: U256, cap: U256) -> (Address, U256) {
+        let auction = self.auctions.get(auction_id);
+        let nft = auction.nft_contract.get();
+        let token_id = auction.token_id.get();
+        match self.view_call(nft, IERC2981::royaltyInfoCall { token_id, sale_price: price }) {
+            Some(info) if info._0 != Address::ZERO && info._1 > U256::ZERO => (info._0, info._1.min(cap)),
+            _ => (Address::ZERO, U256::ZERO),
+        }
+    }
+
+    /// Whether the current standings make a sale: a leader at or above the reserve,
+    /// with enough reveals and commits
+    fn meets_sale_conditions(&self, auction_id: U256) -> bool {
+        let auction = self.auctions.get(auction_id);
+        auction.highest_bidder.get() != Address::ZERO
+            && auction.highest_bid.get() >= auction.reserve_price.get()
+            && auction.reveal_count.get() >= auction.min_reveals.get()
+            && auction.commit_count.get() >= auction.min_commits.get()
+    }
+
+    /// Whether `bidder`'s `bid` would become the new highest bid. A bid equal to the
+    /// highest wins only if `bidder` committed strictly earlier than the current leader.
+    fn outbids_highest(&self, auction_id: U256, bidder: Address, bid: U256) -> bool {
+        let auction = self.auctions.get(auction_id);
+        let highest = auction.highest_bid.get();
+        let leader = auction.highest_bidder.get();
+        if leader == Address::ZERO {
+            return bid > highest && bid >= auction.reserve_price.get();
+        }
+        if bid == highest {
+            return auction.commit_time.get(bidder) < auction.commit_time.get(leader);
+        }
+        // an increment that would overflow can't be met by any bid
+        bid > highest
+            && highest
+                .checked_add(auction.min_increment.get())
+                .is_some_and(|required| bid >= required)
+    }
+
+    /// Amount the winner pays the seller for the current highest bid
+    fn sale_price(&self, auction_id: U256) -> U256 {
+        let auction = self.auctions.get(auction_id);
+        if !auction.second_price.get() {
+            return auction.highest_bid.get();
+        }
+        // a losing bid can sit above the winner when it missed the increment, so cap at the winning bid
+        auction
+            .second_highest_bid
+            .get()
+            .max(auction.reserve_price.get())
+            .min(auction.highest_bid.get())
+    }
+
+    /// Transfer every NFT in the bundle with safety check. Any failed transfer
+    /// reverts the whole call, so the bundle moves atomically.
+    /// ERC721 uses `safeTransferFrom`, so a contract winner that can't receive NFTs
+    /// makes the transfer revert instead of locking the token.
+    fn transfer_nft(&mut self, auction_id: U256, from: Address, to: Address) -> Result<(), SealedBidError> {
+        let auction = self.auctions.get(auction_id);
+        let nft_address = auction.nft_contract.get();
+        let is_erc1155 = auction.is_erc1155.get();
+        let amount = auction.amount.get();
+
+        for token_id in self.bundle(auction_id) {
+            let ok = if is_erc1155 {
+                let transfer = IERC1155::safeTransferFromCall { from, to, id: token_id, amount, data: Bytes::new() };
+                self.mutating_call(nft_address, transfer).is_some()
+            } else {
+                let transfer = IERC721::safeTransferFromCall { from, to, token_id };
+                self.mutating_call(nft_address, transfer).is_some()
+            };
+            if !ok {
+                return Err(SealedBidError::NFTTransferFailed(NFTTransferFailed {}));
+            }
+        }
+        Ok(())
+    }
+
+    /// Token ids of the auction's bundle, copied out of storage so external calls can follow
+    fn bundle(&self, auction_id: U256) -> Vec<U256> {
+        let auction = self.auctions.get(auction_id);
+        (0..auction.token_ids.len())
+            .filter_map(|i| auction.token_ids.get(i))
+            .collect()
+    }
+
+    /// Transfer payment (ETH or the auction's payment token) to `to`
+    fn transfer_payment(&mut self, auction_id: U256, to: Address, amount: U256) -> Result<(), SealedBidError> {
+        if to == Address::ZERO {
+            return Err(SealedBidError::ZeroAddress(ZeroAddress {}));
+        }
+        if amount == U256::ZERO {
+            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+        }
+        if !self.send_funds(auction_id, to, amount) {
+            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
+        }
+        Ok(())
+    }
+
+    /// Send `amount` in the auction's currency, returning whether it succeeded
+    fn send_funds(&mut self, auction_id: U256, to: Address, amount: U256) -> bool {
+        let token = self.auctions.get(auction_id).payment_token.get();
+        self.send_currency(token, to, amount)
+    }
+
+    /// Send `amount` of `token` (zero = ETH), returning whether it succeeded
+    fn send_currency(&mut self, token: Address, to: Address, amount: U256) -> bool {
+        if token == Address::ZERO {
+            return self.vm().transfer_eth(to, amount).is_ok();
+        }
+        let res = self.mutating_call(token, IERC20::transferCall { to, amount });
+        res.is_some_and(|res| res._0)
+    }
+
+    /// Pull `amount` of `token` from `from` into the contract
+    fn pull_funds(&mut self, token: Address, from: Address, amount: U256) -> Result<(), SealedBidError> {
+        let to = self.vm().contract_address();
+        let res = self.mutating_call(token, IERC20::transferFromCall { from, to, amount });
+        if !res.is_some_and(|res| res._0) {
+            return Err(SealedBidError::PaymentFailed(PaymentFailed {}));
         }
         Ok(())
     }
+
+    /// Static-call `to` with `call` and decode what it returns. A revert and malformed
+    /// return data both come back as `None`.
+    fn view_call<C: SolCall>(&self, to: Address, call: C) -> Option<C::Return> {
+        let output = self.vm().static_call(&calls::context::Call::new(), to, &call.abi_encode()).ok()?;
+        C::abi_decode_returns(&output, true).ok()
+    }
+
+    /// Call `to` with `call`, attaching no ETH, and decode what it returns. A revert and
+    /// malformed return data both come back as `None`.
+    fn mutating_call<C: SolCall>(&mut self, to: Address, call: C) -> Option<C::Return> {
+        let output = self.vm().call(&calls::context::Call::new(), to, &call.abi_encode()).ok()?;
+        C::abi_decode_returns(&output, true).ok()
+    }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,2296 @@
+//! Unit tests run against the stylus `TestVM`. The NFT and ERC20 contracts are mocked call
+//! by call, and ETH only moves through the VM's balance table, so every helper below keeps
+//! the contract's balance in step with what a real chain would hold.
+
+use super::*;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::H256;
+use stylus_sdk::alloy_primitives::{b256, keccak256};
+use stylus_sdk::alloy_sol_types::SolEvent;
+use stylus_sdk::stylus_core::host::ConstructorGuard;
+use stylus_sdk::testing::*;
+
+const OWNER: Address = Address::repeat_byte(0x01);
+const SELLER: Address = Address::repeat_byte(0x02);
+const ALICE: Address = Address::repeat_byte(0x0a);
+const BOB: Address = Address::repeat_byte(0x0b);
+const CAROL: Address = Address::repeat_byte(0x0c);
+const NFT: Address = Address::repeat_byte(0xaa);
+const TOKEN: Address = Address::repeat_byte(0xee);
+const CONTRACT: Address = Address::repeat_byte(0xcc);
+
+const TOKEN_ID: u64 = 7;
+const START: u64 = 1_700_000_000;
+const COMMIT_DURATION: u64 = 3_600;
+const REVEAL_DURATION: u64 = 3_600;
+const COMMIT_END: u64 = START + COMMIT_DURATION;
+const REVEAL_END: u64 = COMMIT_END + REVEAL_DURATION;
+
+const RESERVE: u64 = 1_000;
+const DEPOSIT: u64 = 100;
+
+fn u(value: u64) -> U256 {
+    U256::from(value)
+}
+
+/// Every per-auction option `create_auction` takes, defaulting to a plain first-price ETH auction
+struct Setup {
+    token_id: U256,
+    reserve: U256,
+    commit_duration: u64,
+    reveal_duration: u64,
+    min_deposit: U256,
+    second_price: bool,
+    anti_snipe_window: U256,
+    payment_token: Address,
+    is_erc1155: bool,
+    amount: U256,
+    fee_bps: U256,
+    fee_recipient: Address,
+    bond: U256,
+    deterministic_id: bool,
+    rules: AuctionRules,
+    dutch: DutchFallback,
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Setup {
+            token_id: u(TOKEN_ID),
+            reserve: u(RESERVE),
+            commit_duration: COMMIT_DURATION,
+            reveal_duration: REVEAL_DURATION,
+            min_deposit: u(DEPOSIT),
+            second_price: false,
+            anti_snipe_window: U256::ZERO,
+            payment_token: Address::ZERO,
+            is_erc1155: false,
+            amount: U256::ZERO,
+            fee_bps: U256::ZERO,
+            fee_recipient: Address::ZERO,
+            bond: U256::ZERO,
+            deterministic_id: false,
+            rules: AuctionRules {
+                min_increment: U256::ZERO,
+                min_reveals: U256::ZERO,
+                min_commits: U256::ZERO,
+                max_bid: U256::ZERO,
+                allow_reserve_below_deposit: false,
+                forgiving_reveal: false,
+                forfeit_on_invalid: true,
+                auto_credit_losers: false,
+                ordered_reveal: false,
+                multi_bid: false,
+                commit_buffer: U256::ZERO,
+                settle_window: U256::ZERO,
+                nonrevealed_penalty_to_seller: false,
+                deposit_bps: U256::ZERO,
+                private_auction: false,
+                allow_root: B256::ZERO,
+            },
+            dutch: DutchFallback {
+                start_price: U256::ZERO,
+                floor_price: U256::ZERO,
+                decay_per_second: U256::ZERO,
+            },
+        }
+    }
+}
+
+/// A freshly deployed contract at `START`
+fn deploy() -> (TestVM, SealedBidAuction) {
+    deploy_with(Address::ZERO, 0)
+}
+
+/// Like `deploy`, with `weth` as the chain's wrapped ETH and a bidder cap (zero for the default)
+fn deploy_with(weth: Address, max_bidders: u64) -> (TestVM, SealedBidAuction) {
+    let vm = TestVM::default();
+    vm.set_contract_address(CONTRACT);
+    vm.set_block_timestamp(START);
+    vm.set_block_number(1);
+    let mut contract = SealedBidAuction::from(&vm);
+    // the deployment claims the constructor slot before running the constructor
+    contract.check_constructor_slot().unwrap();
+    ok(tx(&vm, OWNER, U256::ZERO, || {
+        contract.constructor(OWNER, u(MIN_RECOVERY_GRACE), weth, u(max_bidders))
+    }));
+    (vm, contract)
+}
+
+/// Run one transaction from `from` carrying `value`. As on chain, a failing call leaves
+/// storage untouched and its ETH stays with the caller; a successful one keeps the ETH.
+fn tx<T>(
+    vm: &TestVM,
+    from: Address,
+    value: U256,
+    call: impl FnOnce() -> Result<T, SealedBidError>,
+) -> Result<T, SealedBidError> {
+    let storage = vm.snapshot().storage;
+    vm.set_sender(from);
+    vm.set_value(value);
+    let res = call();
+    vm.set_value(U256::ZERO);
+    if res.is_ok() {
+        vm.set_balance(CONTRACT, vm.balance(CONTRACT) + value);
+    } else {
+        vm.clear_storage();
+        for (key, word) in storage {
+            vm.set_storage(key, word);
+        }
+    }
+    res
+}
+
+fn ok<T>(res: Result<T, SealedBidError>) -> T {
+    match res {
+        Ok(value) => value,
+        Err(err) => {
+            let data: Vec<u8> = err.into();
+            panic!("call reverted with 0x{}", hex::encode(data));
+        }
+    }
+}
+
+/// Make `NFT` report `owner` as the holder of `token_id`, with the contract approved for all
+fn mock_nft_owner(vm: &TestVM, token_id: U256, owner: Address) {
+    let owner_of = IERC721::ownerOfCall { token_id };
+    vm.mock_static_call(NFT, owner_of.abi_encode(), Ok(IERC721::ownerOfCall::abi_encode_returns(&(owner,))));
+    let approved = IERC721::isApprovedForAllCall { owner, operator: CONTRACT };
+    vm.mock_static_call(NFT, approved.abi_encode(), Ok(IERC721::isApprovedForAllCall::abi_encode_returns(&(true,))));
+}
+
+/// Make `TOKEN` answer `transferFrom(from, CONTRACT, amount)` with `success`
+fn mock_pull(vm: &TestVM, from: Address, amount: u64, success: bool) {
+    let call = IERC20::transferFromCall { from, to: CONTRACT, amount: u(amount) };
+    vm.mock_call(TOKEN, call.abi_encode(), Ok(IERC20::transferFromCall::abi_encode_returns(&(success,))));
+}
+
+/// Make `TOKEN` answer `transfer(to, amount)` with `success`
+fn mock_push(vm: &TestVM, to: Address, amount: u64, success: bool) {
+    let call = IERC20::transferCall { to, amount: u(amount) };
+    vm.mock_call(TOKEN, call.abi_encode(), Ok(IERC20::transferCall::abi_encode_returns(&(success,))));
+}
+
+/// Create an auction of `setup.token_id` from `SELLER` at the current time
+fn create(vm: &TestVM, contract: &mut SealedBidAuction, setup: Setup) -> U256 {
+    ok(try_create(vm, contract, setup))
+}
+
+fn try_create(vm: &TestVM, contract: &mut SealedBidAuction, setup: Setup) -> Result<U256, SealedBidError> {
+    mock_nft_owner(vm, setup.token_id, SELLER);
+    tx(vm, SELLER, setup.bond, || {
+        contract.create_auction(
+            NFT,
+            vec![setup.token_id],
+            setup.reserve,
+            u(setup.commit_duration),
+            u(setup.reveal_duration),
+            setup.min_deposit,
+            setup.second_price,
+            setup.anti_snipe_window,
+            setup.payment_token,
+            setup.is_erc1155,
+            setup.amount,
+            setup.fee_bps,
+            setup.fee_recipient,
+            Address::ZERO,
+            setup.deterministic_id,
+            setup.rules,
+            setup.dutch,
+        )
+    })
+}
+
+/// Nonce each test bidder seals their bids with
+fn nonce_of(bidder: Address) -> U256 {
+    U256::from(bidder.0[0]) + u(1)
+}
+
+fn commit(
+    vm: &TestVM,
+    contract: &mut SealedBidAuction,
+    id: U256,
+    bidder: Address,
+    bid: u64,
+    deposit: u64,
+) -> Result<(), SealedBidError> {
+    let commitment = commitment_hash(bidder, u(bid), nonce_of(bidder));
+    tx(vm, bidder, u(deposit), || contract.commit(id, commitment))
+}
+
+fn reveal(vm: &TestVM, contract: &mut SealedBidAuction, id: U256, bidder: Address, bid: u64) -> Result<(), SealedBidError> {
+    tx(vm, bidder, U256::ZERO, || contract.reveal(id, u(bid), nonce_of(bidder)))
+}
+
+/// Commit one bid per `(bidder, bid)` with the minimum deposit, then reveal them all in order
+fn commit_and_reveal(vm: &TestVM, contract: &mut SealedBidAuction, id: U256, bids: &[(Address, u64)]) {
+    for &(bidder, bid) in bids {
+        ok(commit(vm, contract, id, bidder, bid, DEPOSIT));
+    }
+    vm.set_block_timestamp(COMMIT_END);
+    for &(bidder, bid) in bids {
+        ok(reveal(vm, contract, id, bidder, bid));
+    }
+}
+
+/// After the reveal phase, have `winner` escrow the rest of `bid` and finalize
+fn settle_and_finalize(vm: &TestVM, contract: &mut SealedBidAuction, id: U256, winner: Address, bid: u64) {
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(vm, winner, u(bid - DEPOSIT), || contract.settle(id)));
+    ok(tx(vm, SELLER, U256::ZERO, || contract.finalize(id)));
+}
+
+/// Every `E` emitted so far, in order
+fn events<E: SolEvent>(vm: &TestVM) -> Vec<E> {
+    vm.get_emitted_logs()
+        .into_iter()
+        .filter(|(topics, _)| topics.first() == Some(&E::SIGNATURE_HASH))
+        .map(|(topics, data)| E::decode_raw_log(topics, &data, true).expect("undecodable event"))
+        .collect()
+}
+
+// the event carries the running deposit total and only the hash of the bid
+#[test]
+fn bid_committed_reports_growing_deposit() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_600, 50));
+
+    let committed = events::<BidCommitted>(&vm);
+    assert_eq!(committed.len(), 2);
+    assert_eq!(committed[0].bidder, ALICE);
+    assert_eq!(committed[0].commitment, commitment_hash(ALICE, u(1_500), nonce_of(ALICE)));
+    assert_eq!(committed[0].totalDeposit, u(DEPOSIT));
+    assert_eq!(committed[1].commitment, commitment_hash(ALICE, u(1_600), nonce_of(ALICE)));
+    assert_eq!(committed[1].totalDeposit, u(DEPOSIT + 50));
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT + 50));
+}
+
+// the seller is paid exactly the winning bid out of the winner's own escrow
+#[test]
+fn settle_escrows_the_winning_bid_for_the_seller() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    vm.set_block_timestamp(REVEAL_END);
+
+    // nothing can be paid out before the winner escrows the rest of the bid
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.finalize(id)),
+        Err(SealedBidError::NotSettled(_))
+    ));
+    assert!(matches!(
+        tx(&vm, ALICE, u(1_000), || contract.settle(id)),
+        Err(SealedBidError::IncorrectPayment(_))
+    ));
+    assert!(matches!(
+        tx(&vm, BOB, u(1_100), || contract.settle(id)),
+        Err(SealedBidError::NotWinner(_))
+    ));
+    ok(tx(&vm, ALICE, u(1_500 - DEPOSIT), || contract.settle(id)));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert_eq!(contract.refund_of(id, ALICE), U256::ZERO);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+
+    // the price is only credited once the NFT reaches the winner
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(vm.balance(SELLER), u(1_500));
+    // what is left is exactly the losing bidder's deposit
+    assert_eq!(vm.balance(CONTRACT), u(DEPOSIT));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(BOB), u(DEPOSIT));
+    assert_eq!(vm.balance(CONTRACT), U256::ZERO);
+}
+
+#[test]
+fn first_price_sells_at_the_winning_bid() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200), (CAROL, 1_100)]);
+    assert_eq!(contract.second_highest_bid(id), u(1_200));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+    assert_eq!(contract.refund_of(id, ALICE), U256::ZERO);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert_eq!(contract.refund_of(id, CAROL), u(DEPOSIT));
+}
+
+#[test]
+fn second_price_sells_at_the_runner_up_bid() {
+    let (vm, mut contract) = deploy();
+    let setup = Setup { second_price: true, ..Setup::default() };
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200), (CAROL, 1_100)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    assert_eq!(contract.seller_proceeds(id), u(1_200));
+    // the winner escrowed the full bid and gets the gap to the sale price back
+    assert_eq!(contract.refund_of(id, ALICE), u(300));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert_eq!(contract.refund_of(id, CAROL), u(DEPOSIT));
+}
+
+#[test]
+fn second_price_is_clamped_to_the_reserve() {
+    let (vm, mut contract) = deploy();
+    let setup = Setup { second_price: true, ..Setup::default() };
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 800), (CAROL, 600)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    assert_eq!(contract.seller_proceeds(id), u(RESERVE));
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500 - RESERVE));
+}
+
+#[test]
+fn erc20_auction_pulls_and_pays_in_the_token() {
+    let (vm, mut contract) = deploy();
+    let setup = Setup { payment_token: TOKEN, ..Setup::default() };
+    let id = create(&vm, &mut contract, setup);
+
+    // ETH has no place in a token auction
+    assert!(matches!(
+        commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT),
+        Err(SealedBidError::UnexpectedEther(_))
+    ));
+    // the deposit is pulled with transferFrom, so a refused pull fails the commit
+    mock_pull(&vm, ALICE, DEPOSIT, false);
+    assert!(matches!(
+        commit(&vm, &mut contract, id, ALICE, 1_500, 0),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    mock_pull(&vm, ALICE, DEPOSIT, true);
+    mock_pull(&vm, BOB, DEPOSIT, true);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, 0));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, 0));
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_200));
+
+    // the winner's top-up is pulled too
+    vm.set_block_timestamp(REVEAL_END);
+    mock_pull(&vm, ALICE, 1_500 - DEPOSIT, true);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.settle(id)));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    // refunds and proceeds go out with transfer; a failed transfer keeps the balance owed
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    mock_push(&vm, BOB, DEPOSIT, true);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(contract.refund_of(id, BOB), U256::ZERO);
+
+    mock_push(&vm, SELLER, 1_500, true);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+    assert_eq!(contract.total_deposits(TOKEN), U256::ZERO);
+    assert_eq!(vm.balance(CONTRACT), U256::ZERO);
+}
+
+#[test]
+fn too_few_reveals_fail_the_sale_and_refund_the_leader() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.min_reveals = u(2);
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    assert_eq!(contract.reveal_count(id), u(1));
+
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+
+    assert!(!contract.sold(id));
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+    // the NFT never moves
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)),
+        Err(SealedBidError::NotWinner(_))
+    ));
+    // the would-be winner gets back the deposit and the settled top-up
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(ALICE), u(1_500));
+}
+
+#[test]
+fn auctions_in_one_contract_stay_independent() {
+    let (vm, mut contract) = deploy();
+    let first = create(&vm, &mut contract, Setup::default());
+    let second = create(&vm, &mut contract, Setup { token_id: u(TOKEN_ID + 1), ..Setup::default() });
+    assert_eq!((first, second), (u(0), u(1)));
+    assert_eq!(contract.auction_count(), u(2));
+
+    ok(commit(&vm, &mut contract, first, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, first, BOB, 1_200, DEPOSIT));
+    ok(commit(&vm, &mut contract, second, ALICE, 1_100, DEPOSIT));
+    ok(commit(&vm, &mut contract, second, CAROL, 2_000, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+    // a commitment only opens in the auction it was made in
+    assert!(matches!(
+        reveal(&vm, &mut contract, second, BOB, 1_200),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+    ok(reveal(&vm, &mut contract, first, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, first, BOB, 1_200));
+    ok(reveal(&vm, &mut contract, second, ALICE, 1_100));
+    ok(reveal(&vm, &mut contract, second, CAROL, 2_000));
+
+    assert_eq!((contract.highest_bidder(first), contract.highest_bid(first)), (ALICE, u(1_500)));
+    assert_eq!((contract.highest_bidder(second), contract.highest_bid(second)), (CAROL, u(2_000)));
+    assert_eq!(contract.refund_of(first, ALICE), U256::ZERO);
+    assert_eq!(contract.refund_of(second, ALICE), u(DEPOSIT));
+
+    settle_and_finalize(&vm, &mut contract, first, ALICE, 1_500);
+    assert!(contract.finalized(first));
+    assert!(!contract.finalized(second));
+    settle_and_finalize(&vm, &mut contract, second, CAROL, 2_000);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(first)));
+    ok(tx(&vm, CAROL, U256::ZERO, || contract.claim_nft(second)));
+    assert_eq!(contract.seller_proceeds(first), u(1_500));
+    assert_eq!(contract.seller_proceeds(second), u(2_000));
+}
+
+#[test]
+fn withdraw_refund_rejects_reentry() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+
+    // TestVM can't call back into the contract, so hold the lock the outer withdrawal
+    // takes, which is what a recipient reentering from its fallback would run into
+    contract.locked.set(true);
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)),
+        Err(SealedBidError::Reentrancy(_))
+    ));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+
+    contract.locked.set(false);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert!(!contract.locked.get());
+    assert_eq!(vm.balance(BOB), u(DEPOSIT));
+}
+
+#[test]
+fn cancel_mid_commit_refunds_every_committer() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, 250));
+
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.cancel_auction(id)),
+        Err(SealedBidError::OnlySeller(_))
+    ));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.cancel_auction(id)));
+    assert!(contract.cancelled(id));
+
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(ALICE), u(DEPOSIT));
+    assert_eq!(vm.balance(BOB), u(250));
+    assert_eq!(vm.balance(CONTRACT), U256::ZERO);
+}
+
+#[test]
+fn cancel_is_closed_once_the_reveal_phase_ends() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500)]);
+
+    vm.set_block_timestamp(REVEAL_END);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.cancel_auction(id)),
+        Err(SealedBidError::AuctionNotActive(_))
+    ));
+}
+
+/// Three auctions in which BOB lost and holds a deposit refund of `DEPOSIT`
+fn three_lost_auctions(vm: &TestVM, contract: &mut SealedBidAuction) -> Vec<U256> {
+    let ids: Vec<U256> = (0..3)
+        .map(|i| create(vm, contract, Setup { token_id: u(TOKEN_ID + i), ..Setup::default() }))
+        .collect();
+    for &id in &ids {
+        ok(commit(vm, contract, id, ALICE, 1_500, DEPOSIT));
+        ok(commit(vm, contract, id, BOB, 1_200, DEPOSIT));
+    }
+    vm.set_block_timestamp(COMMIT_END);
+    for &id in &ids {
+        ok(reveal(vm, contract, id, ALICE, 1_500));
+        ok(reveal(vm, contract, id, BOB, 1_200));
+        assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    }
+    ids
+}
+
+#[test]
+fn batch_withdraw_pays_three_auctions_at_once() {
+    let (vm, mut contract) = deploy();
+    let ids = three_lost_auctions(&vm, &mut contract);
+
+    ok(tx(&vm, BOB, U256::ZERO, || contract.batch_withdraw(ids.clone())));
+    assert_eq!(vm.balance(BOB), u(3 * DEPOSIT));
+    for &id in &ids {
+        assert_eq!(contract.refund_of(id, BOB), U256::ZERO);
+    }
+    assert_eq!(events::<RefundWithdrawn>(&vm).len(), 3);
+}
+
+#[test]
+fn failed_batch_withdraw_restores_every_balance() {
+    let (vm, mut contract) = deploy();
+    let ids = three_lost_auctions(&vm, &mut contract);
+
+    // leave the contract unable to cover the transfer. Called outside `tx`, so the
+    // balances seen afterwards are the contract's own restore, not a simulated revert
+    vm.set_balance(CONTRACT, u(2 * DEPOSIT));
+    vm.set_sender(BOB);
+    assert!(matches!(
+        contract.batch_withdraw(ids.clone()),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    assert_eq!(vm.balance(BOB), U256::ZERO);
+    for &id in &ids {
+        assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    }
+}
+
+#[test]
+fn finalize_refunds_the_winner_when_the_nft_left_the_seller() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, ALICE, u(1_500 - DEPOSIT), || contract.settle(id)));
+
+    // the seller sells the NFT elsewhere before the auction closes
+    mock_nft_owner(&vm, u(TOKEN_ID), CAROL);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+
+    let moved = events::<SellerNoLongerOwns>(&vm);
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].seller, SELLER);
+    assert!(contract.finalized(id));
+    assert!(!contract.sold(id));
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(ALICE), u(1_500));
+}
+
+/// Refundable balances of `who` plus every escrowed deposit, which must be covered by the contract's ETH
+fn owed(contract: &SealedBidAuction, id: U256, who: &[Address]) -> U256 {
+    who.iter().map(|&w| contract.refund_of(id, w)).sum::<U256>() + contract.total_deposits(Address::ZERO)
+}
+
+#[test]
+fn outbid_refunds_never_exceed_the_escrowed_eth() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let everyone = [ALICE, BOB, CAROL, SELLER];
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, CAROL, 1_300, DEPOSIT));
+    assert_eq!(owed(&contract, id, &everyone), vm.balance(CONTRACT));
+
+    // each reveal outbids or loses to the one before; only the deposit is ever credited back
+    vm.set_block_timestamp(COMMIT_END);
+    for (bidder, bid) in [(BOB, 1_200), (ALICE, 1_500), (CAROL, 1_300)] {
+        ok(reveal(&vm, &mut contract, id, bidder, bid));
+        assert_eq!(owed(&contract, id, &everyone), vm.balance(CONTRACT));
+    }
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(owed(&contract, id, &everyone) + contract.seller_proceeds(id), vm.balance(CONTRACT));
+
+    for who in [BOB, CAROL] {
+        ok(tx(&vm, who, U256::ZERO, || contract.withdraw_refund(id)));
+    }
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(vm.balance(CONTRACT), U256::ZERO);
+}
+
+#[test]
+fn too_few_commits_fail_the_sale_and_refund_everyone() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.min_commits = u(3);
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+
+    assert!(!contract.sold(id));
+    // even the deposit of a bidder who never revealed comes back
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert_eq!(contract.total_deposits(Address::ZERO), U256::ZERO);
+}
+
+#[test]
+fn a_seller_rejecting_payment_cannot_block_finalize() {
+    let (vm, mut contract) = deploy();
+    let setup = Setup { payment_token: TOKEN, ..Setup::default() };
+    let id = create(&vm, &mut contract, setup);
+    mock_pull(&vm, ALICE, DEPOSIT, true);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, 0));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    vm.set_block_timestamp(REVEAL_END);
+    mock_pull(&vm, ALICE, 1_500 - DEPOSIT, true);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.settle(id)));
+
+    // any payment to the seller reverts, yet the sale completes and only credits them
+    let pay_seller = IERC20::transferCall { to: SELLER, amount: u(1_500) };
+    vm.mock_call(TOKEN, pay_seller.abi_encode(), Err(Vec::new()));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert!(contract.sold(id));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+
+    // once the seller can receive again they pull what they are owed
+    mock_push(&vm, SELLER, 1_500, true);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+}
+
+/// ALICE wins at 1_500 while BOB reveals a bid that doesn't match his commitment
+fn bob_reveals_wrong_bid(forfeit_on_invalid: bool) -> (TestVM, SealedBidAuction, U256) {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.forfeit_on_invalid = forfeit_on_invalid;
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_300));
+    assert_eq!(events::<RevealFailed>(&vm).len(), 1);
+    (vm, contract, id)
+}
+
+#[test]
+fn lenient_policy_refunds_an_invalid_reveal() {
+    let (vm, mut contract, id) = bob_reveals_wrong_bid(false);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert!(events::<DepositForfeited>(&vm).is_empty());
+
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(BOB), u(DEPOSIT));
+}
+
+#[test]
+fn strict_policy_locks_an_invalid_reveal() {
+    let (vm, mut contract, id) = bob_reveals_wrong_bid(true);
+    assert_eq!(contract.refund_of(id, BOB), U256::ZERO);
+    assert_eq!(contract.deposit_of(id, BOB), u(DEPOSIT));
+    assert_eq!(events::<DepositForfeited>(&vm)[0].reason, FORFEIT_HASH_MISMATCH);
+
+    // not even the wedged-auction escape hatch returns it
+    vm.set_block_timestamp(REVEAL_END + MIN_RECOVERY_GRACE + 1);
+    assert!(!contract.can_emergency_withdraw(id, BOB));
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.emergency_withdraw(id)),
+        Err(SealedBidError::NothingToWithdraw(_))
+    ));
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)),
+        Err(SealedBidError::NothingToWithdraw(_))
+    ));
+}
+
+/// ALICE wins over BOB while CAROL commits and never reveals, under a lenient forfeit policy
+fn finalized_with_a_silent_bidder(auto_credit_losers: bool) -> (TestVM, SealedBidAuction, U256) {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.forfeit_on_invalid = false;
+    setup.rules.auto_credit_losers = auto_credit_losers;
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, CAROL, 1_300, DEPOSIT));
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    assert_eq!(contract.refund_of(id, CAROL), U256::ZERO);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    (vm, contract, id)
+}
+
+#[test]
+fn finalize_auto_credits_losers() {
+    let (vm, mut contract, id) = finalized_with_a_silent_bidder(true);
+
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert_eq!(contract.refund_of(id, CAROL), u(DEPOSIT));
+    assert_eq!(contract.credit_cursor(id), u(3));
+    assert_eq!(contract.total_deposits(Address::ZERO), U256::ZERO);
+    ok(tx(&vm, CAROL, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(CAROL), u(DEPOSIT));
+}
+
+#[test]
+fn without_auto_credit_a_silent_deposit_stays_escrowed() {
+    let (_vm, contract, id) = finalized_with_a_silent_bidder(false);
+
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert_eq!(contract.refund_of(id, CAROL), U256::ZERO);
+    assert_eq!(contract.deposit_of(id, CAROL), u(DEPOSIT));
+    assert_eq!(contract.credit_cursor(id), U256::ZERO);
+}
+
+/// `count` distinct bidders, none of them one of the named test accounts
+fn crowd(count: u64) -> Vec<Address> {
+    (0..count).map(|i| Address::left_padding_from(&(0x1000 + i).to_be_bytes())).collect()
+}
+
+/// ALICE wins and is followed in `bidders` by `silent` committers who never reveal
+fn finalized_with_silent_crowd(silent: u64, penalty_to_seller: bool) -> (TestVM, SealedBidAuction, U256) {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.nonrevealed_penalty_to_seller = penalty_to_seller;
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    for bidder in crowd(silent) {
+        ok(commit(&vm, &mut contract, id, bidder, 1_100, DEPOSIT));
+    }
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    (vm, contract, id)
+}
+
+#[test]
+fn sweep_forfeited_pays_a_large_list_over_overlapping_chunks() {
+    let (vm, mut contract, id) = finalized_with_silent_crowd(150, false);
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.sweep_forfeited(id, u(0), u(90))));
+    assert_eq!(contract.swept_cursor(id), u(90));
+    assert_eq!(vm.balance(SELLER), u(89 * DEPOSIT));
+    // the overlap with the first chunk is skipped rather than paid twice
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.sweep_forfeited(id, u(60), u(200))));
+    assert_eq!(contract.swept_cursor(id), u(151));
+    assert_eq!(vm.balance(SELLER), u(150 * DEPOSIT));
+    assert_eq!(contract.total_deposits(Address::ZERO), U256::ZERO);
+
+    // a slice with nothing left is accepted and changes nothing
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.sweep_forfeited(id, u(0), u(151))));
+    assert_eq!(vm.balance(SELLER), u(150 * DEPOSIT));
+}
+
+#[test]
+fn seller_penalty_is_paged_through_credit_losers() {
+    let (vm, mut contract, id) = finalized_with_silent_crowd(150, true);
+
+    // finalize only walks its first chunk of bidders; ALICE is the first of them
+    let chunk = FINALIZE_CREDIT_CHUNK;
+    assert_eq!(contract.swept_cursor(id), u(chunk));
+    assert_eq!(contract.refund_of(id, SELLER), u((chunk - 1) * DEPOSIT));
+
+    ok(tx(&vm, BOB, U256::ZERO, || contract.credit_losers(id, u(chunk - 10), u(100))));
+    assert_eq!(contract.swept_cursor(id), u(151));
+    assert_eq!(contract.refund_of(id, SELLER), u(150 * DEPOSIT));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.credit_losers(id, u(0), u(151))));
+    assert_eq!(contract.refund_of(id, SELLER), u(150 * DEPOSIT));
+    assert_eq!(contract.total_deposits(Address::ZERO), U256::ZERO);
+}
+
+#[test]
+fn non_paying_winner_is_replaced_by_the_runner_up() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.settle_window = u(600);
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    assert_eq!(contract.second_highest_bidder(id), BOB);
+
+    vm.set_block_timestamp(REVEAL_END + 600);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.promote_runner_up(id)),
+        Err(SealedBidError::AuctionNotEnded(_))
+    ));
+    vm.set_block_timestamp(REVEAL_END + 601);
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.promote_runner_up(id)),
+        Err(SealedBidError::OnlySeller(_))
+    ));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.promote_runner_up(id)));
+
+    // the defaulting winner's deposit compensates the seller
+    assert_eq!(contract.refund_of(id, SELLER), u(DEPOSIT));
+    assert_eq!(contract.deposit_of(id, ALICE), U256::ZERO);
+    assert_eq!(events::<DepositForfeited>(&vm)[0].reason, FORFEIT_UNSETTLED);
+    assert_eq!((contract.highest_bidder(id), contract.highest_bid(id)), (BOB, u(1_200)));
+    assert_eq!(contract.settle_deadline(id), u(REVEAL_END + 601 + 600));
+
+    // the runner-up's deposit went back when they lost, so they escrow the whole bid
+    assert!(matches!(
+        tx(&vm, ALICE, u(1_400), || contract.settle(id)),
+        Err(SealedBidError::NotWinner(_))
+    ));
+    ok(tx(&vm, BOB, u(1_200), || contract.settle(id)));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert!(contract.sold(id));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), u(1_200));
+}
+
+/// ALICE seals 1_200 with the minimum deposit and adds a second bid of 1_500 backed by 150
+fn alice_bids_twice(vm: &TestVM, contract: &mut SealedBidAuction) -> U256 {
+    let mut setup = Setup::default();
+    setup.rules.multi_bid = true;
+    let id = create(vm, contract, setup);
+    ok(commit(vm, contract, id, ALICE, 1_200, DEPOSIT));
+    let second = commitment_hash(ALICE, u(1_500), nonce_of(ALICE) + u(1));
+    ok(tx(vm, ALICE, u(150), || contract.commit_additional(id, second)));
+    assert_eq!(contract.commitment_count(id, ALICE), u(2));
+    assert_eq!(contract.bid_deposit_at(id, ALICE, u(0)), u(DEPOSIT));
+    assert_eq!(contract.bid_deposit_at(id, ALICE, u(1)), u(150));
+    id
+}
+
+#[test]
+fn multi_bid_reveals_each_commitment_by_index() {
+    let (vm, mut contract) = deploy();
+    let id = alice_bids_twice(&vm, &mut contract);
+    ok(commit(&vm, &mut contract, id, BOB, 1_300, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+
+    // a wrong opening reverts, whatever the auction's reveal mode
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.reveal_at(id, u(1), u(1_400), nonce_of(ALICE) + u(1))),
+        Err(SealedBidError::CommitmentMismatch(_))
+    ));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.reveal_at(id, u(1), u(1_500), nonce_of(ALICE) + u(1))));
+    assert!(contract.is_bid_revealed(id, ALICE, u(1)));
+    assert!(!contract.is_revealed(id, ALICE));
+    // plain reveal opens the primary bid, which loses and frees only its own deposit
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_200));
+    assert!(contract.is_revealed(id, ALICE));
+    assert_eq!(contract.reveal_count(id), u(1));
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT));
+    assert_eq!(contract.deposit_of(id, ALICE), u(150));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_300));
+
+    assert_eq!((contract.highest_bidder(id), contract.highest_bid(id)), (ALICE, u(1_500)));
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, ALICE, u(1_500 - 150), || contract.settle(id)));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+}
+
+#[test]
+fn reveal_all_opens_every_bid_in_one_call() {
+    let (vm, mut contract) = deploy();
+    let id = alice_bids_twice(&vm, &mut contract);
+    vm.set_block_timestamp(COMMIT_END);
+
+    let nonces = vec![nonce_of(ALICE), nonce_of(ALICE) + u(1)];
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.reveal_all(id, vec![u(1_200)], nonces.clone())),
+        Err(SealedBidError::InvalidAmount(_))
+    ));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.reveal_all(id, vec![u(1_200), u(1_500)], nonces)));
+
+    assert!(contract.is_revealed(id, ALICE));
+    assert_eq!(contract.revealed_bid_of(id, ALICE), u(1_500));
+    assert_eq!((contract.highest_bidder(id), contract.highest_bid(id)), (ALICE, u(1_500)));
+    // the outbid primary bid released its deposit; the leading one stays escrowed
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT));
+    assert_eq!(contract.deposit_of(id, ALICE), u(150));
+    assert_eq!(events::<BidRevealed>(&vm).len(), 2);
+}
+
+fn bonded() -> Setup {
+    Setup { bond: u(301), ..Setup::default() }
+}
+
+#[test]
+fn cancelling_after_commits_splits_the_bond_among_committers() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, bonded());
+    assert_eq!(contract.seller_bond(id), u(301));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.cancel_auction(id)));
+
+    // the first committer also takes the rounding remainder
+    assert_eq!(contract.seller_bond(id), U256::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT + 151));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT + 150));
+    assert_eq!(contract.refund_of(id, SELLER), U256::ZERO);
+    assert_eq!(events::<SellerBondForfeited>(&vm)[0].amount, u(301));
+}
+
+#[test]
+fn honest_finalize_returns_the_bond() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, bonded());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+
+    assert_eq!(contract.refund_of(id, SELLER), u(301));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    assert!(events::<SellerBondForfeited>(&vm).is_empty());
+}
+
+#[test]
+fn moving_the_nft_away_forfeits_the_bond_at_finalize() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, bonded());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, ALICE, u(1_500 - DEPOSIT), || contract.settle(id)));
+    mock_nft_owner(&vm, u(TOKEN_ID), CAROL);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+
+    assert_eq!(contract.refund_of(id, SELLER), U256::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500 + 151));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT + 150));
+}
+
+#[test]
+fn moving_the_nft_away_forfeits_the_bond_in_finalize_unsold() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, bonded());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    mock_nft_owner(&vm, u(TOKEN_ID), CAROL);
+
+    // waiting out the grace period doesn't get the seller out of it either
+    vm.set_block_timestamp(REVEAL_END + MIN_RECOVERY_GRACE + 1);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.finalize_unsold(id)));
+
+    assert_eq!(contract.refund_of(id, SELLER), U256::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT + 151));
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT + 150));
+}
+
+#[test]
+fn a_commitment_hash_belongs_to_its_first_committer() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let alice_hash = commitment_hash(ALICE, u(1_500), nonce_of(ALICE));
+
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, alice_hash)));
+    assert!(matches!(
+        tx(&vm, BOB, u(DEPOSIT), || contract.commit(id, alice_hash)),
+        Err(SealedBidError::CommitmentAlreadyUsed(_))
+    ));
+    assert_eq!(contract.commit_count(id), u(1));
+    // the first committer can still repeat their own hash to add to the deposit
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, alice_hash)));
+    assert_eq!(contract.deposit_of(id, ALICE), u(2 * DEPOSIT));
+
+    // a hash copied ahead of its bidder locks them out of that hash only
+    let bob_hash = commitment_hash(BOB, u(1_200), nonce_of(BOB));
+    ok(tx(&vm, CAROL, u(DEPOSIT), || contract.commit(id, bob_hash)));
+    assert!(matches!(
+        tx(&vm, BOB, u(DEPOSIT), || contract.commit(id, bob_hash)),
+        Err(SealedBidError::CommitmentAlreadyUsed(_))
+    ));
+    let fresh = commitment_hash(BOB, u(1_200), nonce_of(BOB) + u(1));
+    ok(tx(&vm, BOB, u(DEPOSIT), || contract.commit(id, fresh)));
+}
+
+#[test]
+fn one_bidder_cannot_hold_the_same_commitment_twice() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.multi_bid = true;
+    let id = create(&vm, &mut contract, setup);
+    let hash = commitment_hash(ALICE, u(1_500), nonce_of(ALICE));
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, hash)));
+
+    assert!(matches!(
+        tx(&vm, ALICE, u(DEPOSIT), || contract.commit_additional(id, hash)),
+        Err(SealedBidError::CommitmentAlreadyUsed(_))
+    ));
+    // a commit repeating the primary hash only adds to the deposit
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, hash)));
+    assert_eq!(contract.commitment_count(id, ALICE), u(1));
+    assert_eq!(contract.deposit_of(id, ALICE), u(2 * DEPOSIT));
+}
+
+#[test]
+fn the_constructor_sets_up_the_protocol_once() {
+    let vm = TestVM::default();
+    vm.set_contract_address(CONTRACT);
+    vm.set_block_timestamp(START);
+    let mut contract = SealedBidAuction::from(&vm);
+
+    assert!(matches!(
+        tx(&vm, OWNER, U256::ZERO, || contract.constructor(Address::ZERO, u(MIN_RECOVERY_GRACE), Address::ZERO, U256::ZERO)),
+        Err(SealedBidError::ZeroAddress(_))
+    ));
+    assert!(matches!(
+        tx(&vm, OWNER, U256::ZERO, || contract.constructor(OWNER, u(MIN_RECOVERY_GRACE - 1), Address::ZERO, U256::ZERO)),
+        Err(SealedBidError::InvalidDuration(_))
+    ));
+    contract.check_constructor_slot().unwrap();
+    ok(tx(&vm, OWNER, U256::ZERO, || contract.constructor(OWNER, u(MIN_RECOVERY_GRACE), TOKEN, U256::ZERO)));
+    assert_eq!(contract.owner(), OWNER);
+    assert_eq!(contract.weth(), TOKEN);
+    assert_eq!(events::<OwnershipTransferred>(&vm)[0].newOwner, OWNER);
+    ok(try_create(&vm, &mut contract, Setup::default()));
+}
+
+#[test]
+fn lower_reserve_keeps_the_deposit_rule_from_creation() {
+    let (vm, mut contract) = deploy();
+    let strict = create(&vm, &mut contract, Setup::default());
+    let mut setup = Setup { token_id: u(TOKEN_ID + 1), ..Setup::default() };
+    setup.rules.allow_reserve_below_deposit = true;
+    let lenient = create(&vm, &mut contract, setup);
+
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.lower_reserve(strict, u(DEPOSIT - 1))),
+        Err(SealedBidError::ReserveBelowDeposit(_))
+    ));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.lower_reserve(strict, u(DEPOSIT))));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.lower_reserve(lenient, u(DEPOSIT - 1))));
+    assert_eq!(contract.reserve_price(strict), u(DEPOSIT));
+    assert_eq!(contract.reserve_price(lenient), u(DEPOSIT - 1));
+}
+
+#[test]
+fn a_leader_displaced_by_an_earlier_tie_is_told_so() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, BOB, 1_500, DEPOSIT));
+    vm.set_block_timestamp(START + 10);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_500));
+
+    // BOB committed first, so the tie goes to him and ALICE is the one rejected
+    assert_eq!(contract.highest_bidder(id), BOB);
+    let ties = events::<TieRejected>(&vm);
+    assert_eq!(ties.len(), 1);
+    assert_eq!((ties[0].bidder, ties[0].bid), (ALICE, u(1_500)));
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT));
+}
+
+#[test]
+fn ordered_reveal_ignores_bids_below_the_reserve() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.ordered_reveal = true;
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, CAROL, 500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_600, DEPOSIT));
+    vm.set_block_timestamp(COMMIT_END);
+
+    // a cheap low reveal first doesn't set the ceiling for everyone else
+    ok(reveal(&vm, &mut contract, id, CAROL, 500));
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    // but a competing bid above the one before it is out of order
+    assert!(matches!(
+        reveal(&vm, &mut contract, id, BOB, 1_600),
+        Err(SealedBidError::RevealOrderViolation(_))
+    ));
+    assert_eq!(contract.highest_bidder(id), ALICE);
+}
+
+fn half_collateral(payment_token: Address) -> Setup {
+    let mut setup = Setup { payment_token, ..Setup::default() };
+    setup.rules.deposit_bps = u(5_000);
+    setup
+}
+
+#[test]
+fn token_bidders_top_up_to_cover_deposit_bps() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, half_collateral(TOKEN));
+    mock_pull(&vm, ALICE, DEPOSIT, true);
+    mock_pull(&vm, BOB, DEPOSIT, true);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, 0));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, 0));
+
+    // 1_500 at 50% needs 750 locked
+    mock_pull(&vm, ALICE, 650, true);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.top_up_token_deposit(id, u(650))));
+    assert_eq!(contract.deposit_of(id, ALICE), u(750));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_200));
+    assert_eq!(contract.highest_bidder(id), ALICE);
+    // BOB's bid wasn't covered, so it was set aside and his deposit refunded
+    assert_eq!(events::<BidRejectedDeposit>(&vm)[0].bidder, BOB);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+}
+
+#[test]
+fn eth_auctions_have_no_token_top_up() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, half_collateral(Address::ZERO));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.top_up_token_deposit(id, u(650))),
+        Err(SealedBidError::IncorrectPayment(_))
+    ));
+}
+
+#[test]
+fn an_absurd_bid_is_rejected_rather_than_overflowing() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, half_collateral(Address::ZERO));
+    let hash = commitment_hash(ALICE, U256::MAX, nonce_of(ALICE));
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, hash)));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.reveal(id, U256::MAX, nonce_of(ALICE))));
+    assert_eq!(events::<BidRejectedDeposit>(&vm)[0].bid, U256::MAX);
+    assert_eq!(contract.highest_bidder(id), Address::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(DEPOSIT));
+}
+
+#[test]
+fn finalize_unsold_credits_losers_like_finalize() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.forfeit_on_invalid = false;
+    setup.rules.auto_credit_losers = true;
+    let id = create(&vm, &mut contract, setup);
+    ok(commit(&vm, &mut contract, id, CAROL, 1_300, DEPOSIT));
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+
+    // the winner never settles, so nobody can finalize; the escape hatch closes it instead
+    vm.set_block_timestamp(REVEAL_END + MIN_RECOVERY_GRACE);
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.finalize_unsold(id)),
+        Err(SealedBidError::AuctionNotEnded(_))
+    ));
+    vm.set_block_timestamp(REVEAL_END + MIN_RECOVERY_GRACE + 1);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.finalize_unsold(id)));
+
+    assert!(contract.finalized(id));
+    assert!(!contract.sold(id));
+    for who in [ALICE, BOB, CAROL] {
+        assert_eq!(contract.refund_of(id, who), u(DEPOSIT));
+    }
+    assert_eq!(contract.credit_cursor(id), u(3));
+    assert_eq!(contract.total_deposits(Address::ZERO), U256::ZERO);
+}
+
+#[test]
+fn anti_snipe_window_is_bounded_by_the_reveal_phase() {
+    let (vm, mut contract) = deploy();
+    let too_long = Setup { anti_snipe_window: u(REVEAL_DURATION + 1), ..Setup::default() };
+    assert!(matches!(
+        try_create(&vm, &mut contract, too_long),
+        Err(SealedBidError::InvalidDuration(_))
+    ));
+    let id = create(&vm, &mut contract, Setup { anti_snipe_window: u(REVEAL_DURATION), ..Setup::default() });
+    assert_eq!(contract.anti_snipe_window(id), u(REVEAL_DURATION));
+}
+
+#[test]
+fn deterministic_ids_leave_the_sequential_counter_alone() {
+    let (vm, mut contract) = deploy();
+    let hashed = create(&vm, &mut contract, Setup { deterministic_id: true, ..Setup::default() });
+    assert_eq!(hashed, contract.compute_auction_id(SELLER, NFT, u(TOKEN_ID), u(START)));
+    assert_eq!(contract.auction_count(), U256::ZERO);
+
+    let sequential = create(&vm, &mut contract, Setup { token_id: u(TOKEN_ID + 1), ..Setup::default() });
+    assert_eq!(sequential, U256::ZERO);
+    assert_eq!(contract.auction_count(), u(1));
+}
+
+#[test]
+fn a_relayer_funds_a_commitment_the_bidder_reveals() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let alice_hash = commitment_hash(ALICE, u(1_500), nonce_of(ALICE));
+
+    ok(tx(&vm, CAROL, u(DEPOSIT), || contract.commit_for(id, ALICE, alice_hash)));
+    assert_eq!(contract.commitment_of(id, ALICE), alice_hash);
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT));
+    assert_eq!(contract.deposit_of(id, CAROL), U256::ZERO);
+    assert_eq!(events::<BidCommitted>(&vm)[0].bidder, ALICE);
+
+    // the relayer holds no reveal rights, only the bidder does
+    vm.set_block_timestamp(COMMIT_END);
+    assert!(matches!(
+        tx(&vm, CAROL, U256::ZERO, || contract.reveal(id, u(1_500), nonce_of(ALICE))),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    assert_eq!(contract.highest_bidder(id), ALICE);
+}
+
+#[test]
+fn a_third_party_cannot_overwrite_a_commitment() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+
+    let junk = commitment_hash(BOB, u(1), u(1));
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.commit_for(id, ALICE, junk)),
+        Err(SealedBidError::AlreadyCommitted(_))
+    ));
+    assert!(matches!(
+        tx(&vm, BOB, u(DEPOSIT), || contract.commit_for(id, ALICE, junk)),
+        Err(SealedBidError::AlreadyCommitted(_))
+    ));
+    assert_eq!(contract.commitment_of(id, ALICE), commitment_hash(ALICE, u(1_500), nonce_of(ALICE)));
+
+    // ALICE's honest reveal still stands
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    assert!(events::<DepositForfeited>(&vm).is_empty());
+    assert_eq!(contract.highest_bidder(id), ALICE);
+}
+
+#[test]
+fn extend_commit_moves_both_deadlines_by_extra() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.extend_commit(id, u(600))),
+        Err(SealedBidError::OnlySeller(_))
+    ));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.extend_commit(id, u(600))));
+    assert_eq!(contract.commit_end(id), u(COMMIT_END + 600));
+    assert_eq!(contract.reveal_end(id), u(REVEAL_END + 600));
+    let extended = events::<CommitExtended>(&vm);
+    assert_eq!((extended[0].newCommitEnd, extended[0].newRevealEnd), (u(COMMIT_END + 600), u(REVEAL_END + 600)));
+
+    // once the commit phase is over it can no longer be extended
+    vm.set_block_timestamp(COMMIT_END + 600);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.extend_commit(id, u(600))),
+        Err(SealedBidError::CommitPhaseOver(_))
+    ));
+}
+
+#[test]
+fn extend_commit_cannot_exceed_the_duration_ceiling_or_wrap() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+
+    // an `extra` that would wrap reveal_end around to now
+    let wrapping = U256::MAX - u(REVEAL_END - START) + u(1);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.extend_commit(id, wrapping)),
+        Err(SealedBidError::InvalidDuration(_))
+    ));
+    let room = MAX_COMMIT_DURATION - COMMIT_DURATION;
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.extend_commit(id, u(room + 1))),
+        Err(SealedBidError::InvalidDuration(_))
+    ));
+    assert_eq!(contract.reveal_end(id), u(REVEAL_END));
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.extend_commit(id, u(room))));
+    assert_eq!(contract.commit_end(id), u(START + MAX_COMMIT_DURATION));
+}
+
+/// ALICE wins at 1_500 over BOB and finalize closes the sale, with the NFT still unclaimed
+fn finalized_sale(vm: &TestVM, contract: &mut SealedBidAuction) -> U256 {
+    let id = create(vm, contract, Setup::default());
+    commit_and_reveal(vm, contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    settle_and_finalize(vm, contract, id, ALICE, 1_500);
+    assert!(contract.sold(id));
+    id
+}
+
+#[test]
+fn a_rejecting_receiver_blocks_only_its_own_claim() {
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+
+    let transfer = IERC721::safeTransferFromCall { from: SELLER, to: ALICE, token_id: u(TOKEN_ID) };
+    vm.mock_call(NFT, transfer.abi_encode(), Err(Vec::new()));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)),
+        Err(SealedBidError::NFTTransferFailed(_))
+    ));
+    // nothing is owed to the seller until the NFT has moved
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)),
+        Err(SealedBidError::NothingToWithdraw(_))
+    ));
+
+    // the seller can push the NFT once the winner accepts it, and is then paid
+    vm.mock_call(NFT, transfer.abi_encode(), Ok(Vec::new()));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.claim_nft(id)));
+    assert!(contract.nft_claimed(id));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(vm.balance(SELLER), u(1_500));
+}
+
+#[test]
+fn a_seller_who_revokes_approval_refunds_the_winner() {
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+
+    let approved = IERC721::isApprovedForAllCall { owner: SELLER, operator: CONTRACT };
+    vm.mock_static_call(NFT, approved.abi_encode(), Ok(IERC721::isApprovedForAllCall::abi_encode_returns(&(false,))));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    assert!(!contract.sold(id));
+    assert!(!contract.nft_claimed(id));
+    assert_eq!(events::<SellerNoLongerOwns>(&vm)[0].seller, SELLER);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)),
+        Err(SealedBidError::NothingToWithdraw(_))
+    ));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)),
+        Err(SealedBidError::NotWinner(_))
+    ));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(ALICE), u(1_500));
+}
+
+#[test]
+fn a_seller_who_moves_the_nft_after_finalize_refunds_the_winner() {
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+
+    mock_nft_owner(&vm, u(TOKEN_ID), CAROL);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.claim_nft(id)));
+
+    assert_eq!(contract.safe_outcome(id), u(OUTCOME_UNSOLD as u64));
+    assert_eq!(contract.seller_proceeds(id), U256::ZERO);
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500));
+    // the winner and the losing bidder can both be paid back in full
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(CONTRACT), U256::ZERO);
+}
+
+#[test]
+fn weth_auction_pulls_bids_and_pays_the_seller_in_weth() {
+    // TOKEN plays the chain's WETH
+    let (vm, mut contract) = deploy_with(TOKEN, 0);
+    let eth_auction = create(&vm, &mut contract, Setup::default());
+    let id = create(&vm, &mut contract, Setup { payment_token: TOKEN, ..Setup::default() });
+
+    let alice = commitment_hash(ALICE, u(1_500), nonce_of(ALICE));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.commit_weth(eth_auction, alice, u(DEPOSIT))),
+        Err(SealedBidError::NotWethAuction(_))
+    ));
+    mock_pull(&vm, ALICE, DEPOSIT - 1, true);
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.commit_weth(id, alice, u(DEPOSIT - 1))),
+        Err(SealedBidError::NoDeposit(_))
+    ));
+    // the bidder picks how much WETH to lock, here the whole bid
+    mock_pull(&vm, ALICE, 1_500, true);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.commit_weth(id, alice, u(1_500))));
+    mock_pull(&vm, BOB, DEPOSIT, true);
+    let bob = commitment_hash(BOB, u(1_200), nonce_of(BOB));
+    ok(tx(&vm, BOB, U256::ZERO, || contract.commit_weth(id, bob, u(DEPOSIT))));
+    assert_eq!(contract.deposit_of(id, ALICE), u(1_500));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_200));
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.settle(id)));
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+
+    // the seller is paid with a WETH transfer, never by unwrapping into native ETH
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    mock_push(&vm, SELLER, 1_500, true);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.withdraw_proceeds(id)));
+    assert_eq!(events::<ProceedsWithdrawn>(&vm)[0].amount, u(1_500));
+    assert_eq!(vm.balance(SELLER), U256::ZERO);
+}
+
+#[test]
+fn settle_window_is_bounded_like_the_reveal_phase() {
+    let (vm, mut contract) = deploy();
+    let with_window = |window: U256| {
+        let mut setup = Setup::default();
+        setup.rules.settle_window = window;
+        setup
+    };
+    for window in [U256::MAX, u(MAX_REVEAL_DURATION + 1)] {
+        assert!(matches!(
+            try_create(&vm, &mut contract, with_window(window)),
+            Err(SealedBidError::InvalidDuration(_))
+        ));
+    }
+
+    let id = create(&vm, &mut contract, with_window(u(MAX_REVEAL_DURATION)));
+    assert_eq!(contract.settle_deadline(id), u(REVEAL_END + MAX_REVEAL_DURATION));
+}
+
+#[test]
+fn escrow_is_totalled_per_payment_token() {
+    let (vm, mut contract) = deploy();
+    let eth_auction = create(&vm, &mut contract, Setup::default());
+    let token_auction = create(&vm, &mut contract, Setup { payment_token: TOKEN, ..Setup::default() });
+
+    ok(commit(&vm, &mut contract, eth_auction, ALICE, 1_500, 3 * DEPOSIT));
+    mock_pull(&vm, BOB, DEPOSIT, true);
+    ok(commit(&vm, &mut contract, token_auction, BOB, 1_200, 0));
+    assert_eq!(contract.total_deposits(Address::ZERO), u(3 * DEPOSIT));
+    assert_eq!(contract.total_deposits(TOKEN), u(DEPOSIT));
+    // only the ETH escrow is backed by the contract's ETH balance
+    assert_eq!(contract.contract_eth_balance(), contract.total_deposits(Address::ZERO));
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.cancel_auction(token_auction)));
+    assert_eq!(contract.total_deposits(TOKEN), U256::ZERO);
+    assert_eq!(contract.total_deposits(Address::ZERO), u(3 * DEPOSIT));
+}
+
+#[test]
+fn a_higher_bid_short_of_the_increment_does_not_take_the_lead() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.min_increment = u(100);
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_599), (CAROL, 1_600)]);
+    assert_eq!(contract.highest_bidder(id), CAROL);
+    assert_eq!(contract.highest_bid(id), u(1_600));
+
+    // an increment so large that highest + increment overflows is never met
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.min_increment = U256::MAX;
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_000_000)]);
+    assert_eq!(contract.highest_bidder(id), ALICE);
+}
+
+#[test]
+fn pausing_freezes_entry_points_and_shifts_the_timeline() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.pause(id)),
+        Err(SealedBidError::NotOwner(_))
+    ));
+    vm.set_block_timestamp(START + 1_000);
+    ok(tx(&vm, OWNER, U256::ZERO, || contract.pause(id)));
+    assert!(contract.paused(id));
+    assert!(matches!(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT), Err(SealedBidError::Paused(_))));
+    assert!(matches!(reveal(&vm, &mut contract, id, ALICE, 1_500), Err(SealedBidError::Paused(_))));
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)),
+        Err(SealedBidError::Paused(_))
+    ));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)),
+        Err(SealedBidError::Paused(_))
+    ));
+    // views keep answering
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT));
+
+    // both deadlines move back by the 600 seconds spent paused
+    vm.set_block_timestamp(START + 1_600);
+    ok(tx(&vm, OWNER, U256::ZERO, || contract.unpause(id)));
+    assert!(!contract.paused(id));
+    assert_eq!(contract.commit_end(id), u(COMMIT_END + 600));
+    assert_eq!(contract.reveal_end(id), u(REVEAL_END + 600));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+
+    // a pause during the reveal phase only moves the reveal deadline
+    vm.set_block_timestamp(COMMIT_END + 600);
+    ok(tx(&vm, OWNER, U256::ZERO, || contract.pause(id)));
+    vm.set_block_timestamp(COMMIT_END + 900);
+    ok(tx(&vm, OWNER, U256::ZERO, || contract.unpause(id)));
+    assert_eq!(contract.commit_end(id), u(COMMIT_END + 600));
+    assert_eq!(contract.reveal_end(id), u(REVEAL_END + 900));
+    assert!(matches!(
+        tx(&vm, OWNER, U256::ZERO, || contract.unpause(id)),
+        Err(SealedBidError::NotPaused(_))
+    ));
+}
+
+#[test]
+fn phase_durations_must_lie_between_five_minutes_and_ninety_days() {
+    let (vm, mut contract) = deploy();
+    for (commit_duration, reveal_duration) in [
+        (MIN_COMMIT_DURATION - 1, REVEAL_DURATION),
+        (MAX_COMMIT_DURATION + 1, REVEAL_DURATION),
+        (COMMIT_DURATION, MIN_REVEAL_DURATION - 1),
+        (COMMIT_DURATION, MAX_REVEAL_DURATION + 1),
+        (0, REVEAL_DURATION),
+    ] {
+        assert!(matches!(
+            try_create(&vm, &mut contract, Setup { commit_duration, reveal_duration, ..Setup::default() }),
+            Err(SealedBidError::InvalidDuration(_))
+        ));
+    }
+
+    // both bounds are inclusive
+    let shortest = Setup {
+        commit_duration: MIN_COMMIT_DURATION,
+        reveal_duration: MIN_REVEAL_DURATION,
+        ..Setup::default()
+    };
+    let id = create(&vm, &mut contract, shortest);
+    assert_eq!(contract.reveal_end(id), u(START + MIN_COMMIT_DURATION + MIN_REVEAL_DURATION));
+    let longest = Setup {
+        token_id: u(TOKEN_ID + 1),
+        commit_duration: MAX_COMMIT_DURATION,
+        reveal_duration: MAX_REVEAL_DURATION,
+        ..Setup::default()
+    };
+    let id = create(&vm, &mut contract, longest);
+    assert_eq!(contract.commit_end(id), u(START + MAX_COMMIT_DURATION));
+}
+
+#[test]
+fn commit_end_and_reveal_end_each_open_the_next_phase() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+
+    vm.set_block_timestamp(COMMIT_END - 1);
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    assert!(matches!(reveal(&vm, &mut contract, id, ALICE, 1_500), Err(SealedBidError::RevealNotOpen(_))));
+
+    // commit_end itself already belongs to the reveal phase
+    vm.set_block_timestamp(COMMIT_END);
+    assert!(matches!(
+        commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT),
+        Err(SealedBidError::CommitPhaseOver(_))
+    ));
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+
+    vm.set_block_timestamp(REVEAL_END - 1);
+    assert!(matches!(
+        tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)),
+        Err(SealedBidError::AuctionNotEnded(_))
+    ));
+
+    // and reveal_end to the ended one
+    vm.set_block_timestamp(REVEAL_END);
+    assert!(matches!(reveal(&vm, &mut contract, id, ALICE, 1_500), Err(SealedBidError::AuctionNotEnded(_))));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+}
+
+#[test]
+fn phase_follows_the_clock_until_finalize() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let phase_at = |contract: &SealedBidAuction, now: u64| {
+        vm.set_block_timestamp(now);
+        contract.phase(id)
+    };
+
+    assert_eq!(phase_at(&contract, START - 1), u(PHASE_NOT_STARTED as u64));
+    assert_eq!(phase_at(&contract, START), u(PHASE_COMMIT as u64));
+    assert_eq!(phase_at(&contract, COMMIT_END - 1), u(PHASE_COMMIT as u64));
+    assert_eq!(phase_at(&contract, COMMIT_END), u(PHASE_REVEAL as u64));
+    assert_eq!(phase_at(&contract, REVEAL_END - 1), u(PHASE_REVEAL as u64));
+    assert_eq!(phase_at(&contract, REVEAL_END), u(PHASE_ENDED as u64));
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert_eq!(contract.phase(id), u(PHASE_FINALIZED as u64));
+}
+
+/// A `personal_sign` signature by `wallet` over the reveal digest. The TestVM has no
+/// precompiles, so ecrecover is mocked to answer for this signature what the real one would.
+fn sign_reveal(vm: &TestVM, wallet: &LocalWallet, id: U256, bidder: Address, bid: u64, nonce: U256) -> Vec<u8> {
+    let digest = reveal_digest(CONTRACT, id, bidder, u(bid), nonce);
+    let signature = wallet.sign_hash(H256::from_slice(digest.as_slice())).unwrap();
+    let mut input = digest.to_vec();
+    input.extend_from_slice(&u(signature.v).to_be_bytes::<32>());
+    input.extend_from_slice(&signature.to_vec()[..64]);
+    let signer = Address::from_slice(wallet.address().as_bytes());
+    vm.mock_static_call(ECRECOVER, input, Ok(B256::left_padding_from(signer.as_slice()).to_vec()));
+    signature.to_vec()
+}
+
+#[test]
+fn a_relayer_reveals_with_the_bidders_signature() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let wallet = LocalWallet::from_bytes(&[0x11; 32]).unwrap();
+    let bidder = Address::from_slice(wallet.address().as_bytes());
+    let nonce = nonce_of(bidder);
+    let hash = commitment_hash(bidder, u(1_500), nonce);
+    ok(tx(&vm, bidder, u(DEPOSIT), || contract.commit(id, hash)));
+    vm.set_block_timestamp(COMMIT_END);
+
+    // a signature from another key, or over another bid, doesn't recover to the bidder
+    let forger = LocalWallet::from_bytes(&[0x22; 32]).unwrap();
+    let forged = sign_reveal(&vm, &forger, id, bidder, 1_500, nonce);
+    assert!(matches!(
+        tx(&vm, CAROL, U256::ZERO, || contract.reveal_signed(id, bidder, u(1_500), nonce, forged.into())),
+        Err(SealedBidError::InvalidSignature(_))
+    ));
+    let signed = sign_reveal(&vm, &wallet, id, bidder, 1_500, nonce);
+    assert!(matches!(
+        tx(&vm, CAROL, U256::ZERO, || contract.reveal_signed(id, bidder, u(2_000), nonce, signed.clone().into())),
+        Err(SealedBidError::InvalidSignature(_))
+    ));
+    assert!(matches!(
+        tx(&vm, CAROL, U256::ZERO, || contract.reveal_signed(id, bidder, u(1_500), nonce, signed[..64].to_vec().into())),
+        Err(SealedBidError::InvalidSignature(_))
+    ));
+
+    ok(tx(&vm, CAROL, U256::ZERO, || contract.reveal_signed(id, bidder, u(1_500), nonce, signed.into())));
+    assert_eq!(contract.highest_bidder(id), bidder);
+    assert_eq!(events::<BidRevealed>(&vm)[0].bidder, bidder);
+}
+
+/// keccak256 of the sorted pair, the node hash `commit_with_proof` expects
+fn merkle_node(a: B256, b: B256) -> B256 {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([a.as_slice(), b.as_slice()].concat())
+}
+
+#[test]
+fn a_merkle_allowlist_admits_members_with_a_proof() {
+    let (vm, mut contract) = deploy();
+    let dave = Address::repeat_byte(0x0d);
+    let [a, b, c, d] = [ALICE, BOB, CAROL, dave].map(keccak256);
+    let (ab, cd) = (merkle_node(a, b), merkle_node(c, d));
+    let mut setup = Setup::default();
+    setup.rules.allow_root = merkle_node(ab, cd);
+    let id = create(&vm, &mut contract, setup);
+    assert!(contract.get_config(id).2);
+
+    let commit_proving = |contract: &mut SealedBidAuction, bidder: Address, proof: Vec<B256>| {
+        let hash = commitment_hash(bidder, u(1_500), nonce_of(bidder));
+        tx(&vm, bidder, u(DEPOSIT), || contract.commit_with_proof(id, hash, proof))
+    };
+    ok(commit_proving(&mut contract, ALICE, vec![b, cd]));
+    ok(commit_proving(&mut contract, dave, vec![c, ab]));
+
+    // a member without a proof, a member with someone else's, and an outsider are all refused
+    assert!(matches!(commit(&vm, &mut contract, id, CAROL, 1_200, DEPOSIT), Err(SealedBidError::NotAllowlisted(_))));
+    assert!(matches!(commit_proving(&mut contract, BOB, vec![b, cd]), Err(SealedBidError::NotAllowlisted(_))));
+    let outsider = Address::repeat_byte(0x0e);
+    assert!(matches!(
+        commit_proving(&mut contract, outsider, vec![b, cd]),
+        Err(SealedBidError::NotAllowlisted(_))
+    ));
+    assert_eq!(contract.commit_count(id), u(2));
+}
+
+#[test]
+fn commits_from_new_bidders_stop_at_the_cap() {
+    assert_eq!(deploy().1.max_bidders(), u(DEFAULT_MAX_BIDDERS));
+
+    let (vm, mut contract) = deploy_with(Address::ZERO, 3);
+    assert_eq!(contract.max_bidders(), u(3));
+    let id = create(&vm, &mut contract, Setup::default());
+    let bidders = crowd(3);
+    for &bidder in &bidders {
+        ok(commit(&vm, &mut contract, id, bidder, 1_500, DEPOSIT));
+    }
+    assert_eq!(contract.bidder_count(id), u(3));
+
+    assert!(matches!(
+        commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT),
+        Err(SealedBidError::BidderLimitReached(_))
+    ));
+    // bidders already in the list can still recommit and top up
+    ok(commit(&vm, &mut contract, id, bidders[0], 1_600, DEPOSIT));
+    assert_eq!(contract.deposit_of(id, bidders[0]), u(2 * DEPOSIT));
+}
+
+#[test]
+fn a_zero_nonce_reveal_is_rejected_even_when_it_matches() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let hash = commitment_hash(ALICE, u(1_500), U256::ZERO);
+    ok(tx(&vm, ALICE, u(DEPOSIT), || contract.commit(id, hash)));
+
+    vm.set_block_timestamp(COMMIT_END);
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.reveal(id, u(1_500), U256::ZERO)),
+        Err(SealedBidError::WeakNonce(_))
+    ));
+    // the revert leaves the reveal unused and the deposit in place
+    assert!(!contract.is_revealed(id, ALICE));
+    assert_eq!(contract.reveal_count(id), U256::ZERO);
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT));
+}
+
+#[test]
+fn commitment_hash_matches_solidity_encode_packed() {
+    use stylus_sdk::alloy_sol_types::SolValue;
+
+    // keccak256(abi.encodePacked(address, uint256, uint256)): 20 address bytes, then two
+    // big-endian 32-byte words
+    let (bid, nonce) = (u(1_500), u(42));
+    let packed = (ALICE, bid, nonce).abi_encode_packed();
+    assert_eq!(packed.len(), 84);
+    let expected = keccak256(&packed);
+    assert_eq!(expected, b256!("f58ce541e36c5d200f118f96a5d53d518cf7095f685a805aec7b361329eaed43"));
+    assert_eq!(commitment_hash(ALICE, bid, nonce), expected);
+}
+
+/// Make `NFT` report `balance` units of `token_id` held by `SELLER`, as an ERC1155
+fn mock_edition_balance(vm: &TestVM, token_id: U256, balance: u64) {
+    let balance_of = IERC1155::balanceOfCall { account: SELLER, id: token_id };
+    vm.mock_static_call(NFT, balance_of.abi_encode(), Ok(IERC1155::balanceOfCall::abi_encode_returns(&(u(balance),))));
+}
+
+#[test]
+fn erc1155_editions_are_checked_by_balance_and_sent_with_their_amount() {
+    let (vm, mut contract) = deploy();
+    let editions = |amount: u64| Setup { is_erc1155: true, amount: u(amount), ..Setup::default() };
+    assert!(matches!(try_create(&vm, &mut contract, editions(0)), Err(SealedBidError::InvalidAmount(_))));
+    mock_edition_balance(&vm, u(TOKEN_ID), 2);
+    assert!(matches!(try_create(&vm, &mut contract, editions(3)), Err(SealedBidError::NotNFTOwner(_))));
+    mock_edition_balance(&vm, u(TOKEN_ID), 3);
+    let id = create(&vm, &mut contract, editions(3));
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+
+    // delivery goes through the ERC1155 transfer, never the ERC721 one
+    let erc721 = IERC721::safeTransferFromCall { from: SELLER, to: ALICE, token_id: u(TOKEN_ID) };
+    vm.mock_call(NFT, erc721.abi_encode(), Err(Vec::new()));
+    let erc1155 = IERC1155::safeTransferFromCall {
+        from: SELLER,
+        to: ALICE,
+        id: u(TOKEN_ID),
+        amount: u(3),
+        data: Bytes::new(),
+    };
+    vm.mock_call(NFT, erc1155.abi_encode(), Err(Vec::new()));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)),
+        Err(SealedBidError::NFTTransferFailed(_))
+    ));
+    vm.mock_call(NFT, erc1155.abi_encode(), Ok(Vec::new()));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert!(contract.nft_claimed(id));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+}
+
+#[test]
+fn an_erc1155_seller_who_sold_their_editions_refunds_the_winner() {
+    let (vm, mut contract) = deploy();
+    mock_edition_balance(&vm, u(TOKEN_ID), 3);
+    let id = create(&vm, &mut contract, Setup { is_erc1155: true, amount: u(3), ..Setup::default() });
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+
+    mock_edition_balance(&vm, u(TOKEN_ID), 1);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert!(!contract.sold(id));
+    assert_eq!(contract.refund_of(id, ALICE), u(1_500));
+}
+
+#[test]
+fn the_nft_is_delivered_with_safe_transfer_from() {
+    let (vm, mut contract) = deploy();
+    let receiver = Address::repeat_byte(0x0d);
+    let non_receiver = Address::repeat_byte(0x0e);
+    let sales = [(u(TOKEN_ID), receiver), (u(TOKEN_ID + 1), non_receiver)];
+    for (token_id, to) in sales {
+        // a plain transferFrom would go through for either of them...
+        let plain = IERC721::transferFromCall { from: SELLER, to, token_id };
+        vm.mock_call(NFT, plain.abi_encode(), Ok(Vec::new()));
+        // ...but the safe one reverts when the recipient doesn't implement onERC721Received
+        let safe = IERC721::safeTransferFromCall { from: SELLER, to, token_id };
+        vm.mock_call(NFT, safe.abi_encode(), if to == receiver { Ok(Vec::new()) } else { Err(Vec::new()) });
+    }
+
+    let ids = sales.map(|(token_id, _)| create(&vm, &mut contract, Setup { token_id, ..Setup::default() }));
+    for (&id, (_, winner)) in ids.iter().zip(sales) {
+        ok(commit(&vm, &mut contract, id, winner, 1_500, DEPOSIT));
+    }
+    vm.set_block_timestamp(COMMIT_END);
+    for (&id, (_, winner)) in ids.iter().zip(sales) {
+        ok(reveal(&vm, &mut contract, id, winner, 1_500));
+    }
+    for (&id, (_, winner)) in ids.iter().zip(sales) {
+        settle_and_finalize(&vm, &mut contract, id, winner, 1_500);
+    }
+
+    ok(tx(&vm, receiver, U256::ZERO, || contract.claim_nft(ids[0])));
+    assert_eq!(events::<NFTClaimed>(&vm)[0].winner, receiver);
+    assert!(matches!(
+        tx(&vm, non_receiver, U256::ZERO, || contract.claim_nft(ids[1])),
+        Err(SealedBidError::NFTTransferFailed(_))
+    ));
+    assert!(!contract.nft_claimed(ids[1]));
+}
+
+#[test]
+fn an_erc2981_royalty_is_paid_out_of_the_sale() {
+    let creator = Address::repeat_byte(0x0d);
+    let info = IERC2981::royaltyInfoCall { token_id: u(TOKEN_ID), sale_price: u(1_500) };
+    let sell = |royalty: Result<Vec<u8>, Vec<u8>>| {
+        let (vm, mut contract) = deploy();
+        vm.mock_static_call(NFT, info.abi_encode(), royalty);
+        let id = finalized_sale(&vm, &mut contract);
+        ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+        (vm, contract, id)
+    };
+
+    let (vm, contract, id) = sell(Ok(IERC2981::royaltyInfoCall::abi_encode_returns(&(creator, u(150)))));
+    assert_eq!(vm.balance(creator), u(150));
+    assert_eq!(contract.seller_proceeds(id), u(1_350));
+    assert_eq!(contract.settled_price(id), u(1_350));
+
+    // an NFT without ERC2981 reverts the query, and the seller keeps the whole price
+    let (vm, contract, id) = sell(Err(Vec::new()));
+    assert_eq!(vm.balance(creator), U256::ZERO);
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+}
+
+#[test]
+fn the_protocol_fee_is_split_off_the_winning_bid() {
+    let (vm, mut contract) = deploy();
+    let operator = Address::repeat_byte(0x0f);
+    let fee = |fee_bps: u64, fee_recipient: Address| Setup { fee_bps: u(fee_bps), fee_recipient, ..Setup::default() };
+    assert!(matches!(try_create(&vm, &mut contract, fee(1_001, operator)), Err(SealedBidError::InvalidFee(_))));
+    assert!(matches!(try_create(&vm, &mut contract, fee(250, Address::ZERO)), Err(SealedBidError::ZeroAddress(_))));
+
+    // 2.5% of 1_500 is 37.5, rounded down
+    let id = create(&vm, &mut contract, fee(250, operator));
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(vm.balance(operator), u(37));
+    assert_eq!(contract.seller_proceeds(id), u(1_463));
+    let collected = &events::<FeeCollected>(&vm)[0];
+    assert_eq!((collected.recipient, collected.amount), (operator, u(37)));
+
+    // without a fee the seller gets the whole bid and no FeeCollected is logged
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), u(1_500));
+    assert!(events::<FeeCollected>(&vm).is_empty());
+}
+
+#[test]
+fn an_equal_bid_does_not_take_the_lead() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    vm.set_block_timestamp(START + 10);
+    ok(commit(&vm, &mut contract, id, BOB, 1_500, DEPOSIT));
+
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_500));
+    assert_eq!(contract.highest_bidder(id), ALICE);
+    let ties = events::<TieRejected>(&vm);
+    assert_eq!((ties[0].bidder, ties[0].bid), (BOB, u(1_500)));
+
+    // the tied loser is refunded like any other losing bidder
+    assert_eq!(contract.refund_of(id, BOB), u(DEPOSIT));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(BOB), u(DEPOSIT));
+}
+
+#[test]
+fn cancel_commit_returns_the_deposit_and_clears_the_commitment() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, 2 * DEPOSIT));
+    ok(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT));
+    assert!(matches!(
+        tx(&vm, CAROL, U256::ZERO, || contract.cancel_commit(id)),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.cancel_commit(id)));
+    assert_eq!(contract.commitment_of(id, ALICE), B256::ZERO);
+    assert_eq!(contract.deposit_of(id, ALICE), U256::ZERO);
+    assert_eq!(contract.commit_count(id), u(1));
+    assert_eq!(contract.bidder_count(id), u(1));
+    assert_eq!(events::<CommitCancelled>(&vm)[0].refunded, u(2 * DEPOSIT));
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.withdraw_refund(id)));
+    assert_eq!(vm.balance(ALICE), u(2 * DEPOSIT));
+    assert_eq!(contract.total_deposits(Address::ZERO), u(DEPOSIT));
+
+    // nothing is left to cancel, and once the commit phase is over nobody can
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.cancel_commit(id)),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+    vm.set_block_timestamp(COMMIT_END);
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.cancel_commit(id)),
+        Err(SealedBidError::CommitPhaseOver(_))
+    ));
+}
+
+#[test]
+fn top_up_deposit_adds_to_the_escrow_and_keeps_the_commitment() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    assert!(matches!(
+        tx(&vm, ALICE, u(DEPOSIT), || contract.top_up_deposit(id)),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    let sealed = contract.commitment_of(id, ALICE);
+
+    ok(tx(&vm, ALICE, u(400), || contract.top_up_deposit(id)));
+    ok(tx(&vm, ALICE, u(1_000), || contract.top_up_deposit(id)));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.top_up_deposit(id)),
+        Err(SealedBidError::NoDeposit(_))
+    ));
+    assert_eq!(contract.deposit_of(id, ALICE), u(1_500));
+    assert_eq!(contract.commitment_of(id, ALICE), sealed);
+    assert_eq!(vm.balance(CONTRACT), u(1_500));
+
+    // the deposit now covers the bid, so settling needs nothing more
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.settle(id)));
+}
+
+#[test]
+fn recommit_swaps_the_bid_and_grows_the_deposit_at_once() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    let raised = commitment_hash(ALICE, u(2_000), nonce_of(ALICE));
+    assert!(matches!(
+        tx(&vm, ALICE, u(DEPOSIT), || contract.recommit(id, raised)),
+        Err(SealedBidError::NoCommitmentFound(_))
+    ));
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+
+    ok(tx(&vm, ALICE, u(400), || contract.recommit(id, raised)));
+    assert_eq!(contract.commitment_of(id, ALICE), raised);
+    assert_eq!(contract.deposit_of(id, ALICE), u(DEPOSIT + 400));
+    assert_eq!(contract.commit_count(id), u(1));
+    let replaced = &events::<CommitReplaced>(&vm)[0];
+    assert_eq!((replaced.bidder, replaced.newCommitment), (ALICE, raised));
+
+    // the old bid no longer opens, the new one does
+    vm.set_block_timestamp(COMMIT_END);
+    assert!(!contract.verify_reveal(id, u(1_500), nonce_of(ALICE)));
+    ok(reveal(&vm, &mut contract, id, ALICE, 2_000));
+    assert_eq!(contract.highest_bid(id), u(2_000));
+    assert!(matches!(
+        tx(&vm, ALICE, U256::ZERO, || contract.recommit(id, raised)),
+        Err(SealedBidError::CommitPhaseOver(_))
+    ));
+}
+
+/// An auction that drew no bids, finalized at `REVEAL_END` into a dutch sale from 2_000
+/// down to a floor of 500, one wei per second
+fn unsold_into_dutch(vm: &TestVM, contract: &mut SealedBidAuction) -> U256 {
+    let dutch = DutchFallback { start_price: u(2_000), floor_price: u(500), decay_per_second: u(1) };
+    let id = create(vm, contract, Setup { dutch, ..Setup::default() });
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert_eq!(events::<DutchOpened>(vm)[0].startPrice, u(2_000));
+    id
+}
+
+#[test]
+fn a_dutch_buyer_pays_the_decayed_price() {
+    let (vm, mut contract) = deploy();
+    let id = unsold_into_dutch(&vm, &mut contract);
+    assert_eq!(contract.dutch_price(id), u(2_000));
+
+    vm.set_block_timestamp(REVEAL_END + 600);
+    assert_eq!(contract.dutch_price(id), u(1_400));
+    assert!(matches!(
+        tx(&vm, ALICE, u(1_399), || contract.buy_now_dutch(id)),
+        Err(SealedBidError::IncorrectPayment(_))
+    ));
+    ok(tx(&vm, ALICE, u(1_500), || contract.buy_now_dutch(id)));
+    assert_eq!(contract.dutch_buyer(id), ALICE);
+    assert_eq!(events::<DutchPurchase>(&vm)[0].price, u(1_400));
+    assert_eq!(contract.seller_proceeds(id), u(1_400));
+    assert_eq!(contract.refund_of(id, ALICE), u(100));
+    assert!(matches!(
+        tx(&vm, BOB, u(1_500), || contract.buy_now_dutch(id)),
+        Err(SealedBidError::AuctionSold(_))
+    ));
+}
+
+#[test]
+fn the_dutch_price_stops_at_the_floor() {
+    let (vm, mut contract) = deploy();
+    let id = unsold_into_dutch(&vm, &mut contract);
+
+    vm.set_block_timestamp(REVEAL_END + 1_500);
+    assert_eq!(contract.dutch_price(id), u(500));
+    vm.set_block_timestamp(REVEAL_END + 1_000_000);
+    assert_eq!(contract.dutch_price(id), u(500));
+    ok(tx(&vm, ALICE, u(500), || contract.buy_now_dutch(id)));
+    assert_eq!(contract.seller_proceeds(id), u(500));
+}
+
+#[test]
+fn anyone_can_pause_an_auction_whose_nft_left_the_seller() {
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+
+    // while the seller holds the NFT the check changes nothing
+    assert!(ok(tx(&vm, CAROL, U256::ZERO, || contract.check_nft_ownership(id))));
+    assert!(!contract.paused(id));
+
+    mock_nft_owner(&vm, u(TOKEN_ID), CAROL);
+    assert!(!ok(tx(&vm, CAROL, U256::ZERO, || contract.check_nft_ownership(id))));
+    assert!(contract.paused(id));
+    assert_eq!(events::<OwnershipLostPaused>(&vm).len(), 1);
+    assert!(matches!(commit(&vm, &mut contract, id, BOB, 1_200, DEPOSIT), Err(SealedBidError::Paused(_))));
+
+    // tripping it again doesn't log twice
+    assert!(!ok(tx(&vm, BOB, U256::ZERO, || contract.check_nft_ownership(id))));
+    assert_eq!(events::<OwnershipLostPaused>(&vm).len(), 1);
+}
+
+#[test]
+fn preview_settlement_matches_what_the_sale_pays() {
+    let (vm, mut contract) = deploy();
+    let (operator, creator) = (Address::repeat_byte(0x0f), Address::repeat_byte(0x0d));
+    let info = IERC2981::royaltyInfoCall { token_id: u(TOKEN_ID), sale_price: u(1_200) };
+    vm.mock_static_call(NFT, info.abi_encode(), Ok(IERC2981::royaltyInfoCall::abi_encode_returns(&(creator, u(60)))));
+    let setup = Setup { second_price: true, fee_bps: u(1_000), fee_recipient: operator, ..Setup::default() };
+    let id = create(&vm, &mut contract, setup);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+
+    // ALICE wins at BOB's 1_200: 120 fee, 60 royalty, 1_020 for the seller
+    let preview = contract.preview_settlement(id);
+    assert_eq!(preview, (true, ALICE, u(1_020), u(60), u(120)));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert_eq!(contract.seller_proceeds(id), preview.2);
+    assert_eq!(vm.balance(creator), preview.3);
+    assert_eq!(vm.balance(operator), preview.4);
+
+    // a bid under the reserve previews as no sale, and finalize closes it unsold
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 900)]);
+    assert_eq!(contract.preview_settlement(id), (false, Address::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    assert!(!contract.sold(id));
+}
+
+#[test]
+fn a_three_nft_bundle_moves_to_the_winner_all_or_nothing() {
+    let (vm, mut contract) = deploy();
+    let token_ids = vec![u(TOKEN_ID), u(TOKEN_ID + 1), u(TOKEN_ID + 2)];
+    for &token_id in &token_ids {
+        mock_nft_owner(&vm, token_id, SELLER);
+    }
+    let setup = Setup::default();
+    let id = ok(tx(&vm, SELLER, U256::ZERO, || {
+        contract.create_auction(
+            NFT,
+            token_ids.clone(),
+            setup.reserve,
+            u(setup.commit_duration),
+            u(setup.reveal_duration),
+            setup.min_deposit,
+            setup.second_price,
+            setup.anti_snipe_window,
+            setup.payment_token,
+            setup.is_erc1155,
+            setup.amount,
+            setup.fee_bps,
+            setup.fee_recipient,
+            Address::ZERO,
+            setup.deterministic_id,
+            setup.rules,
+            setup.dutch,
+        )
+    }));
+    assert_eq!(contract.token_ids(id), token_ids);
+    commit_and_reveal(&vm, &mut contract, id, &[(ALICE, 1_500), (BOB, 1_200)]);
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+
+    // each id is delivered to ALICE: failing any one of those transfers reverts the claim
+    for &token_id in &token_ids {
+        let transfer = IERC721::safeTransferFromCall { from: SELLER, to: ALICE, token_id };
+        vm.mock_call(NFT, transfer.abi_encode(), Err(Vec::new()));
+        assert!(matches!(
+            tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)),
+            Err(SealedBidError::NFTTransferFailed(_))
+        ));
+        assert!(!contract.nft_claimed(id));
+        vm.mock_call(NFT, transfer.abi_encode(), Ok(Vec::new()));
+    }
+    ok(tx(&vm, ALICE, U256::ZERO, || contract.claim_nft(id)));
+    assert!(contract.nft_claimed(id));
+    assert_eq!(events::<NFTClaimed>(&vm)[0].winner, ALICE);
+}
+
+#[test]
+fn each_reveal_branch_emits_its_own_event() {
+    let (vm, mut contract) = deploy();
+    let dave = Address::repeat_byte(0x0d);
+    let id = create(&vm, &mut contract, Setup::default());
+    for (bidder, bid) in [(ALICE, 1_200), (BOB, 1_500), (CAROL, 1_300), (dave, 1_400)] {
+        ok(commit(&vm, &mut contract, id, bidder, bid, DEPOSIT));
+    }
+    vm.set_block_timestamp(COMMIT_END);
+
+    // the first valid bid leads, replacing nobody
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_200));
+    let highest = events::<NewHighestBid>(&vm);
+    assert_eq!((highest[0].bidder, highest[0].bid, highest[0].previousBidder), (ALICE, u(1_200), Address::ZERO));
+
+    // a higher one takes over and names who it replaced
+    ok(reveal(&vm, &mut contract, id, BOB, 1_500));
+    let highest = events::<NewHighestBid>(&vm);
+    assert_eq!((highest[1].bidder, highest[1].bid, highest[1].previousBidder), (BOB, u(1_500), ALICE));
+
+    // a lower one is only revealed
+    ok(reveal(&vm, &mut contract, id, CAROL, 1_300));
+    assert_eq!(events::<NewHighestBid>(&vm).len(), 2);
+    let revealed = events::<BidRevealed>(&vm);
+    assert_eq!(revealed.len(), 3);
+    assert_eq!((revealed[2].bidder, revealed[2].bid), (CAROL, u(1_300)));
+    assert!(events::<RevealFailed>(&vm).is_empty());
+
+    // a mismatching one fails without revealing anything
+    ok(reveal(&vm, &mut contract, id, dave, 1_450));
+    assert_eq!(events::<RevealFailed>(&vm)[0].bidder, dave);
+    assert_eq!(events::<BidRevealed>(&vm).len(), 3);
+    assert_eq!(events::<NewHighestBid>(&vm).len(), 2);
+}
+
+#[test]
+fn auction_finalized_reports_sold_and_unsold_outcomes() {
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+    let finalized = events::<AuctionFinalized>(&vm);
+    assert_eq!(finalized.len(), 1);
+    assert_eq!(finalized[0].auctionId, id);
+    assert_eq!((finalized[0].winner, finalized[0].winningBid, finalized[0].sold), (ALICE, u(1_500), true));
+
+    // nobody revealed: unsold, with no winner and no price
+    let (vm, mut contract) = deploy();
+    let id = create(&vm, &mut contract, Setup::default());
+    ok(commit(&vm, &mut contract, id, ALICE, 1_500, DEPOSIT));
+    vm.set_block_timestamp(REVEAL_END);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.finalize(id)));
+    let finalized = events::<AuctionFinalized>(&vm);
+    assert_eq!(finalized.len(), 1);
+    assert_eq!((finalized[0].winner, finalized[0].winningBid, finalized[0].sold), (Address::ZERO, U256::ZERO, false));
+}
+
+#[test]
+fn refund_withdrawn_reports_the_credited_amount_only_on_success() {
+    let (vm, mut contract) = deploy();
+    let id = finalized_sale(&vm, &mut contract);
+    let owed = contract.refund_of(id, BOB);
+    assert_eq!(owed, u(DEPOSIT));
+
+    // a failed payout keeps the credit and logs nothing
+    let balance = vm.balance(CONTRACT);
+    vm.set_balance(CONTRACT, U256::ZERO);
+    assert!(matches!(
+        tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)),
+        Err(SealedBidError::PaymentFailed(_))
+    ));
+    assert_eq!(contract.refund_of(id, BOB), owed);
+    assert!(events::<RefundWithdrawn>(&vm).is_empty());
+
+    vm.set_balance(CONTRACT, balance);
+    ok(tx(&vm, BOB, U256::ZERO, || contract.withdraw_refund(id)));
+    let withdrawn = events::<RefundWithdrawn>(&vm);
+    assert_eq!(withdrawn.len(), 1);
+    assert_eq!((withdrawn[0].auctionId, withdrawn[0].who, withdrawn[0].amount), (id, BOB, owed));
+}
+
+#[test]
+fn deposit_forfeited_names_each_reason() {
+    let (vm, mut contract) = deploy();
+    let dave = Address::repeat_byte(0x0d);
+    let mut setup = Setup::default();
+    setup.rules.forfeit_on_invalid = true;
+    let id = create(&vm, &mut contract, setup);
+    for (bidder, bid) in [(ALICE, 1_500), (BOB, 1_200), (CAROL, 1_100), (dave, 1_300)] {
+        ok(commit(&vm, &mut contract, id, bidder, bid, DEPOSIT));
+    }
+    // commit never accepts less than min_deposit, so short dave's deposit by hand
+    contract.auctions.setter(id).deposits.setter(dave).set(u(DEPOSIT - 1));
+    vm.set_block_timestamp(COMMIT_END);
+    ok(reveal(&vm, &mut contract, id, ALICE, 1_500));
+    ok(reveal(&vm, &mut contract, id, BOB, 1_250));
+    ok(reveal(&vm, &mut contract, id, dave, 1_300));
+    settle_and_finalize(&vm, &mut contract, id, ALICE, 1_500);
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.sweep_forfeited(id, u(0), u(4))));
+
+    // CAROL never revealed, so only the sweep reports her
+    let forfeited: Vec<_> = events::<DepositForfeited>(&vm)
+        .into_iter()
+        .map(|event| (event.bidder, event.amount, event.reason))
+        .collect();
+    assert_eq!(
+        forfeited,
+        [
+            (BOB, u(DEPOSIT), FORFEIT_HASH_MISMATCH),
+            (dave, u(DEPOSIT - 1), FORFEIT_INSUFFICIENT_DEPOSIT),
+            (CAROL, u(DEPOSIT), FORFEIT_NEVER_REVEALED),
+        ]
+    );
+}
+
+#[test]
+fn bulk_allowlist_changes_log_every_address() {
+    let (vm, mut contract) = deploy();
+    let mut setup = Setup::default();
+    setup.rules.private_auction = true;
+    let id = create(&vm, &mut contract, setup);
+
+    let accounts = vec![ALICE, BOB, CAROL];
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.add_to_allowlist(id, accounts.clone())));
+    let added: Vec<_> = events::<AllowlistAdded>(&vm).into_iter().map(|event| (event.auctionId, event.bidder)).collect();
+    assert_eq!(added, [(id, ALICE), (id, BOB), (id, CAROL)]);
+
+    ok(tx(&vm, SELLER, U256::ZERO, || contract.remove_from_allowlist(id, vec![ALICE, CAROL])));
+    let removed: Vec<_> =
+        events::<AllowlistRemoved>(&vm).into_iter().map(|event| (event.auctionId, event.bidder)).collect();
+    assert_eq!(removed, [(id, ALICE), (id, CAROL)]);
+    assert!(contract.is_allowlisted(id, BOB));
+    assert!(!contract.is_allowlisted(id, ALICE));
+    assert_eq!(events::<AllowlistAdded>(&vm).len(), 3);
+}
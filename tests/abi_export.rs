@@ -0,0 +1,48 @@
+//! Guards the exported ABI against accidental signature drift.
+//! Run with `cargo test --features export-abi --test abi_export`.
+#![cfg(feature = "export-abi")]
+
+use std::process::Command;
+
+/// Canonical `name(type,...)` signatures (what selectors are hashed from) of every
+/// function in the exported Solidity interface.
+fn exported_signatures() -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_sealed_bid_auction"))
+        .output()
+        .expect("failed to run ABI export");
+    assert!(output.status.success(), "ABI export exited with {}", output.status);
+
+    let abi = String::from_utf8(output.stdout).expect("ABI export is not UTF-8");
+    abi.lines()
+        .filter_map(|line| line.trim().strip_prefix("function "))
+        .map(|decl| {
+            let (name, rest) = decl.split_once('(').expect("function without parameter list");
+            let params = &rest[..rest.find(')').expect("unterminated parameter list")];
+            let types: Vec<&str> = params
+                .split(',')
+                .filter_map(|param| param.split_whitespace().next())
+                .collect();
+            format!("{}({})", name, types.join(","))
+        })
+        .collect()
+}
+
+#[test]
+fn exports_core_functions() {
+    let signatures = exported_signatures();
+    for expected in [
+        "commit(uint256,bytes32)",
+        "reveal(uint256,uint256,uint256)",
+        "settle(uint256)",
+        "finalize(uint256)",
+        "withdrawRefund(uint256)",
+        "claimNft(uint256)",
+        "cancelAuction(uint256)",
+    ] {
+        assert!(
+            signatures.iter().any(|s| s == expected),
+            "missing `{}` in exported ABI",
+            expected
+        );
+    }
+}